@@ -0,0 +1,87 @@
+use std::{
+    env,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+#[path = "src/render/shader_compiler.rs"]
+mod shader_compiler;
+
+//compiles every shaders/*.glsl to spir-v and generates $OUT_DIR/shaders.rs, which embeds them as SHADERS
+fn main() {
+    let shader_dir = Path::new("shaders");
+    println!("cargo:rerun-if-changed={}", shader_dir.display());
+    println!("cargo:rerun-if-changed=src/render/shader_compiler.rs");
+
+    let mut paths: Vec<_> = fs::read_dir(shader_dir)
+        .expect("Failed to read the shader directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "glsl")
+        })
+        .collect();
+    paths.sort();
+
+    let compiler = shaderc::Compiler::new().expect("Failed to create the shader compiler");
+    let options = shader_compiler::compile_options().expect("Failed to create compile options");
+
+    let mut generated = String::from("pub static SHADERS: &[(&str, &[u32])] = &[\n");
+    let mut errors = Vec::new();
+
+    for path in &paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        match compile(&compiler, &options, path) {
+            Ok(code) => {
+                let name = path.with_extension("spv");
+                let name = name.file_name().unwrap().to_string_lossy();
+
+                write!(generated, "    (\"{}\", &[", name).unwrap();
+                for (index, word) in code.iter().enumerate() {
+                    if index % 8 == 0 {
+                        generated.push_str("\n        ");
+                    }
+                    write!(generated, "{:#010x}, ", word).unwrap();
+                }
+                generated.push_str("\n    ]),\n");
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+
+    generated.push_str("];\n");
+
+    //all shaders are compiled first, so every broken one is reported at once
+    if !errors.is_empty() {
+        for error in &errors {
+            eprintln!("{}\n", error);
+        }
+        eprintln!("{} shader(s) failed to compile", errors.len());
+        process::exit(1);
+    }
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::write(out_dir.join("shaders.rs"), generated).unwrap();
+}
+
+fn compile(
+    compiler: &shaderc::Compiler,
+    options: &shaderc::CompileOptions,
+    path: &Path,
+) -> Result<Vec<u32>, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("{}: failed to read: {}", path.display(), e))?;
+
+    let artifact = shader_compiler::compile(compiler, options, path, &source)?;
+
+    if artifact.get_num_warnings() > 0 {
+        for line in artifact.get_warning_messages().lines() {
+            println!("cargo:warning={}", line);
+        }
+    }
+
+    Ok(artifact.as_binary().to_vec())
+}
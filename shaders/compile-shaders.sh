@@ -1,2 +1,9 @@
+#only needed for the shaders-from-disk feature, build.rs embeds the shaders otherwise
+glslangValidator -V ./example.task.glsl -o ../bin/example.task.spv
 glslangValidator -V ./example.mesh.glsl -o ../bin/example.mesh.spv
-glslangValidator -V ./example.frag.glsl -o ../bin/example.frag.spv
\ No newline at end of file
+glslangValidator -V --target-env spirv1.4 ./example.ext.task.glsl -o ../bin/example.ext.task.spv
+glslangValidator -V --target-env spirv1.4 ./example.ext.mesh.glsl -o ../bin/example.ext.mesh.spv
+glslangValidator -V ./example.vert.glsl -o ../bin/example.vert.spv
+glslangValidator -V ./example.frag.glsl -o ../bin/example.frag.spv
+glslangValidator -V ./output.vert.glsl -o ../bin/output.vert.spv
+glslangValidator -V ./output.frag.glsl -o ../bin/output.frag.spv
@@ -0,0 +1,84 @@
+#version 460
+
+#extension GL_EXT_mesh_shader : require
+
+layout(local_size_x = 32) in;
+layout(triangles, max_vertices = 64, max_primitives = 126) out;
+
+layout(location = 0) out vec3[] outColors;
+
+struct Vertex {
+    float px, py, pz;
+    float nx, ny, nz;
+    float u, v;
+};
+
+struct Meshlet {
+    uint vertexOffset;
+    uint primitiveOffset;
+    uint vertexCount;
+    uint primitiveCount;
+};
+
+layout(set = 0, binding = 0) uniform UBO {
+    mat4 finalMatrix;
+    vec4 frustumPlanes[6];
+    vec3 cameraPosition;
+} ubo;
+
+//keep in sync with geometry::DrawPushConstants
+layout(push_constant) uniform DrawPushConstants {
+    mat4 modelMatrix;
+    vec4 baseColorFactor;
+    uint meshletOffset;
+    uint meshletCount;
+} draw;
+
+layout(set = 0, binding = 1) readonly buffer Vertices {
+    Vertex vertices[];
+};
+
+layout(set = 0, binding = 2) readonly buffer Meshlets {
+    Meshlet meshlets[];
+};
+
+layout(set = 0, binding = 3) readonly buffer VertexIndices {
+    uint vertexIndices[];
+};
+
+layout(set = 0, binding = 4) readonly buffer PrimitiveIndices {
+    uint primitiveIndices[];
+};
+
+taskPayloadSharedEXT Task {
+    uint meshletIndices[32];
+} IN;
+
+uint loadPrimitiveIndex(uint index) {
+    return (primitiveIndices[index >> 2] >> ((index & 3) * 8)) & 0xFF;
+}
+
+void main() {
+    Meshlet meshlet = meshlets[IN.meshletIndices[gl_WorkGroupID.x]];
+
+    SetMeshOutputsEXT(meshlet.vertexCount, meshlet.primitiveCount);
+
+    mat3 normalMatrix = transpose(inverse(mat3(draw.modelMatrix)));
+
+    for(uint i = gl_LocalInvocationID.x; i < meshlet.vertexCount; i += gl_WorkGroupSize.x) {
+        Vertex vertex = vertices[vertexIndices[meshlet.vertexOffset + i]];
+
+        gl_MeshVerticesEXT[i].gl_Position = ubo.finalMatrix * draw.modelMatrix * vec4(vertex.px, vertex.py, vertex.pz, 1.0);
+        vec3 normal = normalize(normalMatrix * vec3(vertex.nx, vertex.ny, vertex.nz));
+        outColors[i] = (normal * 0.5 + 0.5) * draw.baseColorFactor.rgb;
+    }
+
+    for(uint i = gl_LocalInvocationID.x; i < meshlet.primitiveCount; i += gl_WorkGroupSize.x) {
+        uint offset = meshlet.primitiveOffset + i * 3;
+        gl_PrimitiveTriangleIndicesEXT[i] = uvec3(
+            loadPrimitiveIndex(offset),
+            loadPrimitiveIndex(offset + 1),
+            loadPrimitiveIndex(offset + 2)
+        );
+    }
+}
@@ -0,0 +1,81 @@
+#version 460
+
+#extension GL_EXT_mesh_shader : require
+
+layout(local_size_x = 32) in;
+
+struct MeshletBounds {
+    vec3 center;
+    float radius;
+    vec3 coneAxis;
+    float coneCutoff;
+};
+
+layout(set = 0, binding = 0) uniform UBO {
+    mat4 finalMatrix;
+    vec4 frustumPlanes[6];
+    vec3 cameraPosition;
+} ubo;
+
+//keep in sync with geometry::DrawPushConstants
+layout(push_constant) uniform DrawPushConstants {
+    mat4 modelMatrix;
+    vec4 baseColorFactor;
+    uint meshletOffset;
+    uint meshletCount;
+} draw;
+
+layout(set = 0, binding = 5) readonly buffer MeshletBoundsBuffer {
+    MeshletBounds meshletBounds[];
+};
+
+taskPayloadSharedEXT Task {
+    uint meshletIndices[32];
+} OUT;
+
+shared uint visibleCount;
+
+//keep in sync with culling::transform_bounds and culling::is_meshlet_visible
+bool isMeshletVisible(MeshletBounds bounds) {
+    vec3 scales = vec3(
+        length(draw.modelMatrix[0].xyz),
+        length(draw.modelMatrix[1].xyz),
+        length(draw.modelMatrix[2].xyz)
+    );
+    float maxScale = max(max(scales.x, scales.y), scales.z);
+    float minScale = min(min(scales.x, scales.y), scales.z);
+
+    vec3 center = (draw.modelMatrix * vec4(bounds.center, 1.0)).xyz;
+    float radius = bounds.radius * maxScale;
+
+    for(int i = 0; i < 6; i++) {
+        if(dot(ubo.frustumPlanes[i].xyz, center) + ubo.frustumPlanes[i].w < -radius) {
+            return false;
+        }
+    }
+
+    //the cone does not survive a non-uniform scale
+    if(maxScale - minScale > maxScale * 1e-3 || bounds.coneCutoff >= 1.0) {
+        return true;
+    }
+
+    vec3 coneAxis = normalize(mat3(draw.modelMatrix) * bounds.coneAxis);
+    vec3 direction = center - ubo.cameraPosition;
+    return dot(direction, coneAxis) < bounds.coneCutoff * length(direction) + radius;
+}
+
+void main() {
+    if(gl_LocalInvocationID.x == 0) {
+        visibleCount = 0;
+    }
+    barrier();
+
+    //the meshlets of the draw are a range of the shared buffers
+    uint meshletIndex = draw.meshletOffset + gl_GlobalInvocationID.x;
+    if(gl_GlobalInvocationID.x < draw.meshletCount && isMeshletVisible(meshletBounds[meshletIndex])) {
+        OUT.meshletIndices[atomicAdd(visibleCount, 1)] = meshletIndex;
+    }
+    barrier();
+
+    EmitMeshTasksEXT(visibleCount, 1, 1);
+}
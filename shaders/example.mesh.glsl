@@ -2,27 +2,80 @@
 
 #extension GL_NV_mesh_shader : require
 
-layout(local_size_x = 1) in;
-layout(triangles, max_vertices = 3, max_primitives = 1) out;
+layout(local_size_x = 32) in;
+layout(triangles, max_vertices = 64, max_primitives = 126) out;
 
 layout(location = 0) out vec3[] outColors;
 
+struct Vertex {
+    float px, py, pz;
+    float nx, ny, nz;
+    float u, v;
+};
+
+struct Meshlet {
+    uint vertexOffset;
+    uint primitiveOffset;
+    uint vertexCount;
+    uint primitiveCount;
+};
+
 layout(set = 0, binding = 0) uniform UBO {
     mat4 finalMatrix;
+    vec4 frustumPlanes[6];
+    vec3 cameraPosition;
 } ubo;
 
+//keep in sync with geometry::DrawPushConstants
+layout(push_constant) uniform DrawPushConstants {
+    mat4 modelMatrix;
+    vec4 baseColorFactor;
+    uint meshletOffset;
+    uint meshletCount;
+} draw;
+
+layout(set = 0, binding = 1) readonly buffer Vertices {
+    Vertex vertices[];
+};
+
+layout(set = 0, binding = 2) readonly buffer Meshlets {
+    Meshlet meshlets[];
+};
+
+layout(set = 0, binding = 3) readonly buffer VertexIndices {
+    uint vertexIndices[];
+};
+
+layout(set = 0, binding = 4) readonly buffer PrimitiveIndices {
+    uint primitiveIndices[];
+};
+
+taskNV in Task {
+    uint meshletIndices[32];
+} IN;
+
+uint loadPrimitiveIndex(uint index) {
+    return (primitiveIndices[index >> 2] >> ((index & 3) * 8)) & 0xFF;
+}
+
 void main() {
-    gl_PrimitiveCountNV = 1;
+    Meshlet meshlet = meshlets[IN.meshletIndices[gl_WorkGroupID.x]];
+
+    mat3 normalMatrix = transpose(inverse(mat3(draw.modelMatrix)));
+
+    for(uint i = gl_LocalInvocationID.x; i < meshlet.vertexCount; i += gl_WorkGroupSize.x) {
+        Vertex vertex = vertices[vertexIndices[meshlet.vertexOffset + i]];
 
-    gl_MeshVerticesNV[0].gl_Position = ubo.finalMatrix * vec4(-0.5, -0.5, 0.0, 1.0);
-    gl_MeshVerticesNV[1].gl_Position = ubo.finalMatrix * vec4(0.5, -0.5, 0.0, 1.0);
-    gl_MeshVerticesNV[2].gl_Position = ubo.finalMatrix * vec4(0.0, 0.5, 0.0, 1.0);
+        gl_MeshVerticesNV[i].gl_Position = ubo.finalMatrix * draw.modelMatrix * vec4(vertex.px, vertex.py, vertex.pz, 1.0);
+        vec3 normal = normalize(normalMatrix * vec3(vertex.nx, vertex.ny, vertex.nz));
+        outColors[i] = (normal * 0.5 + 0.5) * draw.baseColorFactor.rgb;
+    }
 
-    outColors[0] = vec3(1.0, 0.0, 0.0);
-    outColors[1] = vec3(0.0, 1.0, 0.0);
-    outColors[2] = vec3(0.0, 0.0, 1.0);
+    for(uint i = gl_LocalInvocationID.x; i < meshlet.primitiveCount * 3; i += gl_WorkGroupSize.x) {
+        gl_PrimitiveIndicesNV[i] = loadPrimitiveIndex(meshlet.primitiveOffset + i);
+    }
 
-    gl_PrimitiveIndicesNV[0] = 0;
-    gl_PrimitiveIndicesNV[1] = 1;
-    gl_PrimitiveIndicesNV[2] = 2;
-}
\ No newline at end of file
+    if(gl_LocalInvocationID.x == 0) {
+        gl_PrimitiveCountNV = meshlet.primitiveCount;
+    }
+}
@@ -0,0 +1,27 @@
+#version 460
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inNormal;
+layout(location = 2) in vec2 inTexCoord;
+
+layout(location = 0) out vec3 outColor;
+
+layout(set = 0, binding = 0) uniform UBO {
+    mat4 finalMatrix;
+    vec4 frustumPlanes[6];
+    vec3 cameraPosition;
+} ubo;
+
+//keep in sync with geometry::DrawPushConstants
+layout(push_constant) uniform DrawPushConstants {
+    mat4 modelMatrix;
+    vec4 baseColorFactor;
+    uint meshletOffset;
+    uint meshletCount;
+} draw;
+
+void main() {
+    gl_Position = ubo.finalMatrix * draw.modelMatrix * vec4(inPosition, 1.0);
+    vec3 normal = normalize(transpose(inverse(mat3(draw.modelMatrix))) * inNormal);
+    outColor = (normal * 0.5 + 0.5) * draw.baseColorFactor.rgb;
+}
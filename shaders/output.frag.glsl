@@ -0,0 +1,66 @@
+#version 460
+
+layout(location = 0) out vec4 outColor;
+
+layout(set = 0, binding = 0) uniform sampler2D sceneColor;
+
+//must match OutputTransform::shader_value
+const uint TRANSFORM_SRGB = 0;
+const uint TRANSFORM_SRGB_ENCODE = 1;
+const uint TRANSFORM_HDR10 = 2;
+const uint TRANSFORM_SCRGB = 3;
+
+layout(push_constant) uniform PushConstants {
+    uint transform;
+    float paperWhiteNits;
+} pc;
+
+//columns of the rec. 709 to rec. 2020 primaries conversion
+const mat3 REC709_TO_REC2020 = mat3(
+    0.6274, 0.0691, 0.0164,
+    0.3293, 0.9195, 0.0880,
+    0.0433, 0.0114, 0.8956
+);
+
+//krzysztof narkowicz's fit of the aces filmic curve
+vec3 tonemapAces(vec3 color) {
+    return clamp((color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14), 0.0, 1.0);
+}
+
+vec3 encodeSrgb(vec3 color) {
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(low, high, greaterThan(color, vec3(0.0031308)));
+}
+
+//smpte st 2084, nits are absolute and clipped at 10000
+vec3 encodePq(vec3 nits) {
+    const float m1 = 0.1593017578125;
+    const float m2 = 78.84375;
+    const float c1 = 0.8359375;
+    const float c2 = 18.8515625;
+    const float c3 = 18.6875;
+
+    vec3 y = pow(clamp(nits / 10000.0, 0.0, 1.0), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
+
+void main() {
+    //the target has the same size as the scene color, so no filtering is needed
+    vec3 color = max(texelFetch(sceneColor, ivec2(gl_FragCoord.xy), 0).rgb, vec3(0.0));
+
+    switch (pc.transform) {
+    case TRANSFORM_SRGB:
+        outColor = vec4(tonemapAces(color), 1.0);
+        break;
+    case TRANSFORM_SRGB_ENCODE:
+        outColor = vec4(encodeSrgb(tonemapAces(color)), 1.0);
+        break;
+    case TRANSFORM_HDR10:
+        outColor = vec4(encodePq(REC709_TO_REC2020 * color * pc.paperWhiteNits), 1.0);
+        break;
+    case TRANSFORM_SCRGB:
+        outColor = vec4(color * (pc.paperWhiteNits / 80.0), 1.0);
+        break;
+    }
+}
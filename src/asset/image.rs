@@ -0,0 +1,113 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use anyhow::{bail, ensure, Context, Result};
+
+//tightly packed rgba8, rows from top to bottom
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self> {
+        ensure!(
+            pixels.len() == 4 * width as usize * height as usize,
+            "Expected {} bytes for a {}x{} rgba8 image, got {}",
+            4 * width as usize * height as usize,
+            width,
+            height,
+            pixels.len()
+        );
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+        Self::read_png(BufReader::new(file))
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    //palette, grayscale and 16 bit images are converted to rgba8
+    pub fn read_png(reader: impl Read) -> Result<Self> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer
+                .iter()
+                .flat_map(|&value| [value, value, value, 255])
+                .collect(),
+            color_type => bail!("Unsupported color type {:?}", color_type),
+        };
+
+        Self::new(info.width, info.height, pixels)
+    }
+
+    //the format is chosen by the extension, .ppm is written as binary ppm and everything else as png
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let writer = BufWriter::new(file);
+
+        let is_ppm = path
+            .extension()
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("ppm"));
+        if is_ppm {
+            self.write_ppm(writer)
+        } else {
+            self.write_png(writer)
+        }
+        .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn write_png(&self, writer: impl Write) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+
+        Ok(())
+    }
+
+    //ppm has no alpha channel, it is dropped
+    pub fn write_ppm(&self, mut writer: impl Write) -> Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in self.pixels.chunks_exact(4) {
+            writer.write_all(&pixel[..3])?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
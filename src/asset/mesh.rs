@@ -0,0 +1,71 @@
+use glam::{Vec2, Vec3};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct Vertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub tex_coord: Vec2,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MeshGroup {
+    pub name: String,
+    pub index_offset: u32,
+    pub index_count: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub groups: Vec<MeshGroup>,
+}
+
+impl Mesh {
+    pub fn triangle() -> Self {
+        let vertex = |x, y, u, v| Vertex {
+            position: Vec3::new(x, y, 0.0),
+            normal: Vec3::new(0.0, 0.0, -1.0),
+            tex_coord: Vec2::new(u, v),
+        };
+
+        Self {
+            vertices: vec![
+                vertex(-0.5, -0.5, 0.0, 1.0),
+                vertex(0.5, -0.5, 1.0, 1.0),
+                vertex(0.0, 0.5, 0.5, 0.0),
+            ],
+            indices: vec![0, 1, 2],
+            groups: vec![MeshGroup {
+                name: String::from("triangle"),
+                index_offset: 0,
+                index_count: 3,
+            }],
+        }
+    }
+
+    pub fn positions(&self) -> Vec<Vec3> {
+        self.vertices.iter().map(|v| v.position).collect()
+    }
+
+    pub fn compute_normals(&mut self) {
+        self.vertices.iter_mut().for_each(|v| v.normal = Vec3::ZERO);
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+
+            //not normalized, so larger triangles contribute more
+            let normal = (self.vertices[b].position - self.vertices[a].position)
+                .cross(self.vertices[c].position - self.vertices[a].position);
+
+            self.vertices[a].normal += normal;
+            self.vertices[b].normal += normal;
+            self.vertices[c].normal += normal;
+        }
+
+        self.vertices
+            .iter_mut()
+            .for_each(|v| v.normal = v.normal.normalize_or_zero());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod image;
+pub mod mesh;
+pub mod obj;
+pub mod scene;
+
+pub use mesh::*;
@@ -0,0 +1,230 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::{FromStr, SplitWhitespace},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use glam::{Vec2, Vec3};
+
+use crate::asset::{Mesh, MeshGroup, Vertex};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct FaceVertex {
+    position: usize,
+    tex_coord: Option<usize>,
+    normal: Option<usize>,
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Mesh> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+    parse(BufReader::new(file)).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn parse(reader: impl BufRead) -> Result<Mesh> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut tex_coords = Vec::new();
+
+    let mut mesh = Mesh::default();
+    let mut vertex_map = HashMap::new();
+    //per vertex, faces may mix vertices with and without normals
+    let mut missing_normals = Vec::new();
+
+    let mut face = Vec::new();
+
+    for (line_index, line) in reader.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.with_context(|| format!("line {}: failed to read", line_number))?;
+
+        let line = match line.find('#') {
+            Some(comment) => &line[..comment],
+            None => &line,
+        };
+
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        let result = (|| -> Result<()> {
+            match keyword {
+                "v" => positions.push(Vec3::new(
+                    parse_next(&mut tokens)?,
+                    parse_next(&mut tokens)?,
+                    parse_next(&mut tokens)?,
+                )),
+                "vn" => normals.push(Vec3::new(
+                    parse_next(&mut tokens)?,
+                    parse_next(&mut tokens)?,
+                    parse_next(&mut tokens)?,
+                )),
+                "vt" => {
+                    let u = parse_next(&mut tokens)?;
+                    //v is optional for 1D textures
+                    let v = match tokens.next() {
+                        Some(token) => parse_float(token)?,
+                        None => 0.0,
+                    };
+                    tex_coords.push(Vec2::new(u, v));
+                }
+                "f" => {
+                    face.clear();
+                    for token in tokens {
+                        face.push(parse_face_vertex(
+                            token,
+                            positions.len(),
+                            tex_coords.len(),
+                            normals.len(),
+                        )?);
+                    }
+                    if face.len() < 3 {
+                        bail!("face has {} vertices, expected at least 3", face.len());
+                    }
+
+                    let mut indices = Vec::with_capacity(face.len());
+                    for face_vertex in &face {
+                        let index = *vertex_map.entry(*face_vertex).or_insert_with(|| {
+                            missing_normals.push(face_vertex.normal.is_none());
+                            mesh.vertices.push(Vertex {
+                                position: positions[face_vertex.position],
+                                normal: face_vertex.normal.map_or(Vec3::ZERO, |i| normals[i]),
+                                tex_coord: face_vertex
+                                    .tex_coord
+                                    .map_or(Vec2::ZERO, |i| tex_coords[i]),
+                            });
+                            mesh.vertices.len() as u32 - 1
+                        });
+                        indices.push(index);
+                    }
+
+                    //triangle fan, obj polygons are expected to be convex
+                    for i in 1..indices.len() - 1 {
+                        mesh.indices
+                            .extend_from_slice(&[indices[0], indices[i], indices[i + 1]]);
+                    }
+                }
+                "g" | "o" => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    begin_group(&mut mesh, name);
+                }
+                //materials, smoothing groups, lines and curves are not supported
+                _ => {}
+            }
+            Ok(())
+        })();
+
+        result.with_context(|| format!("line {}", line_number))?;
+    }
+
+    end_group(&mut mesh);
+    mesh.groups.retain(|group| group.index_count > 0);
+
+    //keep the normals from the file and only fill in the missing ones
+    if missing_normals.contains(&true) {
+        let file_normals: Vec<_> = mesh.vertices.iter().map(|v| v.normal).collect();
+        mesh.compute_normals();
+
+        mesh.vertices
+            .iter_mut()
+            .zip(file_normals)
+            .zip(missing_normals)
+            .filter(|(_, missing)| !missing)
+            .for_each(|((vertex, normal), _)| vertex.normal = normal);
+    }
+
+    Ok(mesh)
+}
+
+fn begin_group(mesh: &mut Mesh, name: String) {
+    end_group(mesh);
+
+    mesh.groups.push(MeshGroup {
+        name,
+        index_offset: mesh.indices.len() as u32,
+        index_count: 0,
+    });
+}
+
+fn end_group(mesh: &mut Mesh) {
+    let index_count = mesh.indices.len() as u32;
+
+    match mesh.groups.last_mut() {
+        Some(group) => group.index_count = index_count - group.index_offset,
+        //faces before the first g/o statement go into an unnamed group
+        None if index_count > 0 => mesh.groups.push(MeshGroup {
+            name: String::new(),
+            index_offset: 0,
+            index_count,
+        }),
+        None => {}
+    }
+}
+
+fn parse_next(tokens: &mut SplitWhitespace) -> Result<f32> {
+    parse_float(tokens.next().ok_or_else(|| anyhow!("missing component"))?)
+}
+
+fn parse_float(token: &str) -> Result<f32> {
+    f32::from_str(token).with_context(|| format!("invalid number {:?}", token))
+}
+
+fn parse_face_vertex(
+    token: &str,
+    position_count: usize,
+    tex_coord_count: usize,
+    normal_count: usize,
+) -> Result<FaceVertex> {
+    let mut parts = token.split('/');
+
+    let position = resolve_index(parts.next().unwrap_or(""), position_count)
+        .with_context(|| format!("invalid position index in {:?}", token))?;
+
+    let tex_coord = match parts.next() {
+        Some("") | None => None,
+        Some(part) => Some(
+            resolve_index(part, tex_coord_count)
+                .with_context(|| format!("invalid texture coordinate index in {:?}", token))?,
+        ),
+    };
+
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(part) => Some(
+            resolve_index(part, normal_count)
+                .with_context(|| format!("invalid normal index in {:?}", token))?,
+        ),
+    };
+
+    if parts.next().is_some() {
+        bail!("too many components in face vertex {:?}", token);
+    }
+
+    Ok(FaceVertex {
+        position,
+        tex_coord,
+        normal,
+    })
+}
+
+//obj indices are one-based, negative indices are relative to the end of the list
+fn resolve_index(part: &str, count: usize) -> Result<usize> {
+    let index = i64::from_str(part)?;
+
+    let resolved = match index {
+        0 => bail!("index 0 is not valid"),
+        i if i > 0 => i - 1,
+        i => count as i64 + i,
+    };
+
+    if resolved < 0 || resolved as usize >= count {
+        bail!("index {} is out of range for {} elements", index, count);
+    }
+
+    Ok(resolved as usize)
+}
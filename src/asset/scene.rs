@@ -0,0 +1,448 @@
+use std::{iter, mem, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use gltf::{buffer, image, mesh::Mode, Document, Gltf};
+
+use crate::asset::{Mesh, MeshGroup, Vertex};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextureRef {
+    pub texture: usize,
+    pub tex_coord: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageSource {
+    Uri(String),
+    BufferView {
+        buffer: usize,
+        offset: usize,
+        length: usize,
+        mime_type: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Texture {
+    pub name: Option<String>,
+    pub source: ImageSource,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlphaMode {
+    Opaque,
+    Mask,
+    Blend,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: Option<String>,
+
+    pub base_color_factor: Vec4,
+    pub base_color_texture: Option<TextureRef>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<TextureRef>,
+
+    pub normal_texture: Option<TextureRef>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<TextureRef>,
+    pub occlusion_strength: f32,
+    pub emissive_factor: Vec3,
+    pub emissive_texture: Option<TextureRef>,
+
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        DEFAULT_MATERIAL.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Primitive {
+    pub mesh: Mesh,
+    pub material: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SceneMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<Primitive>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: Option<String>,
+    pub local_transform: Mat4,
+    pub world_transform: Mat4,
+    pub mesh: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub nodes: Vec<Node>,
+    pub root_nodes: Vec<usize>,
+    pub meshes: Vec<SceneMesh>,
+    pub materials: Vec<Material>,
+    pub textures: Vec<Texture>,
+}
+
+pub struct Draw<'a> {
+    pub transform: Mat4,
+    //indices into Scene::meshes and SceneMesh::primitives
+    pub mesh_index: usize,
+    pub primitive_index: usize,
+    pub primitive: &'a Primitive,
+    pub material: &'a Material,
+}
+
+impl Scene {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let gltf =
+            Gltf::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+        Self::from_gltf(gltf, path.parent())
+            .with_context(|| format!("Failed to import {}", path.display()))
+    }
+
+    //a single root node that draws mesh untransformed with the default material
+    pub fn from_mesh(mesh: Mesh) -> Self {
+        Self {
+            nodes: vec![Node {
+                name: None,
+                local_transform: Mat4::IDENTITY,
+                world_transform: Mat4::IDENTITY,
+                mesh: Some(0),
+                children: Vec::new(),
+            }],
+            root_nodes: vec![0],
+            meshes: vec![SceneMesh {
+                name: None,
+                primitives: vec![Primitive {
+                    mesh,
+                    material: None,
+                }],
+            }],
+            materials: Vec::new(),
+            textures: Vec::new(),
+        }
+    }
+
+    //accepts both .gltf and .glb, relative uris can only be resolved if base is given
+    pub fn from_slice(slice: &[u8], base: Option<&Path>) -> Result<Self> {
+        Self::from_gltf(Gltf::from_slice(slice)?, base)
+    }
+
+    fn from_gltf(gltf: Gltf, base: Option<&Path>) -> Result<Self> {
+        let Gltf { document, blob } = gltf;
+        let buffers = gltf::import_buffers(&document, base, blob)?;
+
+        let textures = document.textures().map(import_texture).collect();
+        let materials = document.materials().map(import_material).collect();
+        let meshes = document
+            .meshes()
+            .map(|mesh| import_mesh(&mesh, &buffers))
+            .collect::<Result<_>>()?;
+
+        let nodes = document
+            .nodes()
+            .map(|node| Node {
+                name: node.name().map(str::to_owned),
+                local_transform: Mat4::from_cols_array_2d(&node.transform().matrix()),
+                world_transform: Mat4::IDENTITY,
+                mesh: node.mesh().map(|mesh| mesh.index()),
+                children: node.children().map(|child| child.index()).collect(),
+            })
+            .collect();
+
+        let mut scene = Self {
+            nodes,
+            root_nodes: root_nodes(&document),
+            meshes,
+            materials,
+            textures,
+        };
+        scene.update_world_transforms()?;
+
+        Ok(scene)
+    }
+
+    //fails if a node is reached twice, i.e. the hierarchy contains a cycle or shared children
+    pub fn update_world_transforms(&mut self) -> Result<()> {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut stack: Vec<_> = self
+            .root_nodes
+            .iter()
+            .map(|node| (*node, Mat4::IDENTITY))
+            .collect();
+
+        while let Some((index, parent_transform)) = stack.pop() {
+            let node = self
+                .nodes
+                .get_mut(index)
+                .ok_or_else(|| anyhow!("node {} does not exist", index))?;
+            if mem::replace(&mut visited[index], true) {
+                bail!(
+                    "node {} is reachable more than once, the node hierarchy must be a forest",
+                    index
+                );
+            }
+
+            node.world_transform = parent_transform * node.local_transform;
+
+            let world_transform = node.world_transform;
+            stack.extend(node.children.iter().map(|child| (*child, world_transform)));
+        }
+
+        Ok(())
+    }
+
+    //only nodes reachable from the root nodes are drawn, using the transforms of the last update
+    pub fn draws(&self) -> impl Iterator<Item = Draw<'_>> {
+        self.reachable_nodes()
+            .filter_map(|node| Some((node.world_transform, node.mesh?)))
+            .flat_map(move |(transform, mesh_index)| {
+                self.meshes[mesh_index].primitives.iter().enumerate().map(
+                    move |(primitive_index, primitive)| Draw {
+                        transform,
+                        mesh_index,
+                        primitive_index,
+                        primitive,
+                        material: primitive
+                            .material
+                            .map_or(&DEFAULT_MATERIAL, |material| &self.materials[material]),
+                    },
+                )
+            })
+    }
+
+    //depth first from the root nodes, visits every node at most once even if the hierarchy is malformed
+    fn reachable_nodes(&self) -> impl Iterator<Item = &Node> {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut stack: Vec<_> = self.root_nodes.iter().rev().copied().collect();
+
+        iter::from_fn(move || loop {
+            let index = stack.pop()?;
+            if visited.get(index) == Some(&false) {
+                visited[index] = true;
+
+                let node = &self.nodes[index];
+                stack.extend(node.children.iter().rev());
+                return Some(node);
+            }
+        })
+    }
+}
+
+//the default material as defined by the glTF 2.0 specification
+static DEFAULT_MATERIAL: Material = Material {
+    name: None,
+
+    base_color_factor: Vec4::ONE,
+    base_color_texture: None,
+    metallic_factor: 1.0,
+    roughness_factor: 1.0,
+    metallic_roughness_texture: None,
+
+    normal_texture: None,
+    normal_scale: 1.0,
+    occlusion_texture: None,
+    occlusion_strength: 1.0,
+    emissive_factor: Vec3::ZERO,
+    emissive_texture: None,
+
+    alpha_mode: AlphaMode::Opaque,
+    alpha_cutoff: 0.5,
+    double_sided: false,
+};
+
+fn root_nodes(document: &Document) -> Vec<usize> {
+    match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(scene) => scene.nodes().map(|node| node.index()).collect(),
+        //without any scene, every node that is not referenced as a child is a root
+        None => {
+            let mut is_child = vec![false; document.nodes().len()];
+            document
+                .nodes()
+                .flat_map(|node| node.children())
+                .for_each(|child| is_child[child.index()] = true);
+
+            (0..is_child.len()).filter(|i| !is_child[*i]).collect()
+        }
+    }
+}
+
+fn import_texture(texture: gltf::Texture) -> Texture {
+    let source = match texture.source().source() {
+        image::Source::Uri { uri, .. } => ImageSource::Uri(uri.to_owned()),
+        image::Source::View { view, mime_type } => ImageSource::BufferView {
+            buffer: view.buffer().index(),
+            offset: view.offset(),
+            length: view.length(),
+            mime_type: mime_type.to_owned(),
+        },
+    };
+
+    Texture {
+        name: texture.name().map(str::to_owned),
+        source,
+    }
+}
+
+fn texture_ref(info: gltf::texture::Info) -> TextureRef {
+    TextureRef {
+        texture: info.texture().index(),
+        tex_coord: info.tex_coord(),
+    }
+}
+
+fn import_material(material: gltf::Material) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    let normal_texture = material.normal_texture();
+    let occlusion_texture = material.occlusion_texture();
+
+    Material {
+        name: material.name().map(str::to_owned),
+
+        base_color_factor: Vec4::from(pbr.base_color_factor()),
+        base_color_texture: pbr.base_color_texture().map(texture_ref),
+        metallic_factor: pbr.metallic_factor(),
+        roughness_factor: pbr.roughness_factor(),
+        metallic_roughness_texture: pbr.metallic_roughness_texture().map(texture_ref),
+
+        normal_texture: normal_texture.as_ref().map(|info| TextureRef {
+            texture: info.texture().index(),
+            tex_coord: info.tex_coord(),
+        }),
+        normal_scale: normal_texture.as_ref().map_or(1.0, |info| info.scale()),
+        occlusion_texture: occlusion_texture.as_ref().map(|info| TextureRef {
+            texture: info.texture().index(),
+            tex_coord: info.tex_coord(),
+        }),
+        occlusion_strength: occlusion_texture
+            .as_ref()
+            .map_or(1.0, |info| info.strength()),
+        emissive_factor: Vec3::from(material.emissive_factor()),
+        emissive_texture: material.emissive_texture().map(texture_ref),
+
+        alpha_mode: match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+        double_sided: material.double_sided(),
+    }
+}
+
+fn import_mesh(mesh: &gltf::Mesh, buffers: &[buffer::Data]) -> Result<SceneMesh> {
+    let primitives = mesh
+        .primitives()
+        .map(|primitive| {
+            import_primitive(&primitive, buffers)
+                .with_context(|| format!("primitive {}", primitive.index()))
+        })
+        .collect::<Result<_>>()
+        .with_context(|| format!("mesh {}", mesh.index()))?;
+
+    Ok(SceneMesh {
+        name: mesh.name().map(str::to_owned),
+        primitives,
+    })
+}
+
+fn import_primitive(primitive: &gltf::Primitive, buffers: &[buffer::Data]) -> Result<Primitive> {
+    if primitive.mode() != Mode::Triangles {
+        bail!("unsupported primitive mode {:?}", primitive.mode());
+    }
+
+    //the reader takes care of strides, sparse substitution and normalized integer types
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &*data.0));
+
+    let positions: Vec<Vec3> = reader
+        .read_positions()
+        .ok_or_else(|| anyhow!("missing POSITION attribute"))?
+        .map(Vec3::from)
+        .collect();
+
+    let mut vertices: Vec<_> = positions
+        .iter()
+        .map(|position| Vertex {
+            position: *position,
+            ..Default::default()
+        })
+        .collect();
+
+    if let Some(normals) = reader.read_normals() {
+        check_count("NORMAL", normals.len(), vertices.len())?;
+        vertices
+            .iter_mut()
+            .zip(normals)
+            .for_each(|(vertex, normal)| vertex.normal = Vec3::from(normal));
+    }
+
+    if let Some(tex_coords) = reader.read_tex_coords(0) {
+        let tex_coords = tex_coords.into_f32();
+        check_count("TEXCOORD_0", tex_coords.len(), vertices.len())?;
+        vertices
+            .iter_mut()
+            .zip(tex_coords)
+            .for_each(|(vertex, tex_coord)| vertex.tex_coord = Vec2::from(tex_coord));
+    }
+
+    let indices: Vec<u32> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..vertices.len() as u32).collect(),
+    };
+    if indices.len() % 3 != 0 {
+        bail!("index count {} is not a multiple of three", indices.len());
+    }
+    if let Some(index) = indices.iter().find(|i| **i as usize >= vertices.len()) {
+        bail!(
+            "index {} is out of range for {} vertices",
+            index,
+            vertices.len()
+        );
+    }
+
+    let mut mesh = Mesh {
+        vertices,
+        groups: vec![MeshGroup {
+            name: String::new(),
+            index_offset: 0,
+            index_count: indices.len() as u32,
+        }],
+        indices,
+    };
+    if reader.read_normals().is_none() {
+        mesh.compute_normals();
+    }
+
+    Ok(Primitive {
+        mesh,
+        material: primitive.material().index(),
+    })
+}
+
+fn check_count(attribute: &str, count: usize, expected: usize) -> Result<()> {
+    if count != expected {
+        bail!(
+            "{} has {} elements, but POSITION has {}",
+            attribute,
+            count,
+            expected
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,3 @@
+pub mod asset;
+pub mod render;
+pub mod timing;
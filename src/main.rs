@@ -1,5 +1,14 @@
+use anyhow::{anyhow, bail};
 use glam::Vec3;
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    env, fmt,
+    fs::File,
+    io::BufWriter,
+    mem, process,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 use winit::event::{DeviceEvent, ElementState, VirtualKeyCode};
 use winit::{
     dpi::{PhysicalSize, Size},
@@ -9,20 +18,105 @@ use winit::{
     window::WindowBuilder,
 };
 
-use crate::render::{render_ctx::RenderCtx, renderer, Camera};
+use vulkan_experinments::{
+    asset::{image::Image, obj, scene::Scene, Mesh},
+    render::{
+        config::{self, RenderConfig},
+        render_ctx::RenderCtx,
+        renderer, Camera,
+    },
+    timing::{FixedTimestep, FrameLimiter, FrameTimer},
+};
+
+#[cfg(feature = "hot-reload")]
+use std::borrow::Cow;
+#[cfg(feature = "hot-reload")]
+use vulkan_experinments::render::shader_reload::ShaderWatcher;
+
+const HEADLESS_WIDTH: u32 = 1600;
+const HEADLESS_HEIGHT: u32 = 900;
+
+const WINDOW_TITLE: &str = "Vulkan experiments";
+const TITLE_UPDATE_INTERVAL: Duration = Duration::from_millis(500);
 
-pub mod render;
+//cycles between 1 and MAX_FRAMES_IN_FLIGHT frames in flight, to compare latency and throughput
+const FRAMES_IN_FLIGHT_KEY: VirtualKeyCode = VirtualKeyCode::F;
+const MAX_FRAMES_IN_FLIGHT: usize = 3;
 
+//camera movement is simulated in fixed steps, short enough to look smooth without interpolation
+const SIMULATION_STEP: Duration = Duration::from_micros(4167);
+const MAX_SIMULATION_STEPS_PER_FRAME: u32 = 30;
+
+const USAGE: &str =
+    "usage: vulkan_experinments [--headless <output.png|output.ppm>] [--gpu-trace <trace.json>]
+    [--pipeline-statistics] [--validation] [--present-mode <fifo|fifo-relaxed|mailbox|immediate>]
+    [--frames-in-flight <count>] [--max-fps <fps>] [--hdr <hdr10|scrgb>] [--paper-white <nits>]
+    [--device <index|name>]
+    [mesh.obj|scene.gltf|scene.glb]
+press F to cycle the number of frames in flight";
+
+struct Args {
+    headless_output: Option<String>,
+    gpu_trace_output: Option<String>,
+    print_pipeline_statistics: bool,
+    config: RenderConfig,
+    frame_limiter: Option<FrameLimiter>,
+    mesh_path: Option<String>,
+}
+
+//validation defaults to on in debug builds, see RenderConfig::from_env, and is logged through RUST_LOG
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let args: Vec<_> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let Args {
+        headless_output,
+        gpu_trace_output,
+        print_pipeline_statistics,
+        config,
+        frame_limiter,
+        mesh_path,
+    } = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{:#}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let scene = match load_scene(mesh_path.as_ref()) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            process::exit(1);
+        }
+    };
+
+    if let Some(output) = headless_output {
+        render_headless(&scene, &output, &config).unwrap();
+        return;
+    }
+
     let mut event_loop = EventLoop::new();
     let window = WindowBuilder::new()
-        .with_title("Vulkan experiments")
+        .with_title(WINDOW_TITLE)
         .with_inner_size(Size::Physical(PhysicalSize::new(1600, 900)))
-        .with_resizable(false)
         .build(&event_loop)
         .unwrap();
 
-    let mut render_ctx = RenderCtx::new(&window);
+    let mut render_ctx = match RenderCtx::new(&window, &config) {
+        Ok(render_ctx) => render_ctx,
+        Err(e) => {
+            eprintln!("Failed to initialize Vulkan: {}", e);
+            process::exit(1);
+        }
+    };
+    render_ctx.pipeline_statistics_enabled = print_pipeline_statistics;
+    render_ctx.set_scene(&scene).unwrap();
 
     let mut frame_count = 0;
     let mut frame_index = 0;
@@ -32,6 +126,21 @@ fn main() {
     let mut pressed_keys = HashSet::new();
     let mut camera = Camera::new(Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 0.0));
 
+    let mut resized = false;
+    //set if the loop ended because of an error
+    let mut failed = false;
+
+    #[cfg(feature = "hot-reload")]
+    let mut shader_watcher =
+        ShaderWatcher::new(concat!(env!("CARGO_MANIFEST_DIR"), "/shaders")).unwrap();
+
+    let mut frame_timer = FrameTimer::default();
+    let mut simulation = FixedTimestep::new(SIMULATION_STEP, MAX_SIMULATION_STEPS_PER_FRAME);
+    //fifo modes are already paced by the display
+    let mut frame_limiter = frame_limiter.filter(|_| config::is_uncapped(render_ctx.present_mode));
+    let mut cycle_frames_in_flight = false;
+    let mut last_title_update = Instant::now();
+
     while running {
         event_loop.run_return(|event, _, control_flow| {
             *control_flow = ControlFlow::Wait;
@@ -40,13 +149,20 @@ fn main() {
                 Event::WindowEvent { event, window_id } => {
                     if window.id() == window_id {
                         match event {
-                            WindowEvent::Resized(_) => {} //TODO: handle
+                            WindowEvent::Resized(_) => resized = true,
                             WindowEvent::CloseRequested => running = false,
                             WindowEvent::KeyboardInput { input, .. } => {
                                 if let Some(key_code) = input.virtual_keycode {
                                     if key_code == VirtualKeyCode::Escape {
                                         running = false;
                                     }
+                                    //only on the initial press, not on key repeat
+                                    if key_code == FRAMES_IN_FLIGHT_KEY
+                                        && input.state == ElementState::Pressed
+                                        && !pressed_keys.contains(&key_code)
+                                    {
+                                        cycle_frames_in_flight = true;
+                                    }
 
                                     match input.state {
                                         ElementState::Pressed => {
@@ -80,13 +196,215 @@ fn main() {
             }
         });
 
-        unsafe {
-            let delta = 1.0 / 165.0; //TODO: dont do this
-            camera.update(&pressed_keys, delta);
-            renderer::render_frame(&mut render_ctx, &mut frame_index, &camera);
+        if let Some(frame_limiter) = &mut frame_limiter {
+            frame_limiter.wait();
+        }
+
+        if mem::take(&mut cycle_frames_in_flight) {
+            let frames_in_flight = render_ctx.frames.len() % MAX_FRAMES_IN_FLIGHT + 1;
+            //a failure leaves the frames that were created so far, which are still usable
+            match render_ctx.set_frames_in_flight(frames_in_flight) {
+                Ok(()) => log::info!("{} frames in flight", frames_in_flight),
+                Err(e) => log::error!(
+                    "Failed to change the number of frames in flight to {}: {:#}",
+                    frames_in_flight,
+                    e
+                ),
+            }
+            frame_index = frame_count % render_ctx.frames.len();
+        }
+
+        //ticked even while minimized, so the first frame afterwards does not see the whole pause as its delta
+        frame_timer.tick();
+        if last_title_update.elapsed() >= TITLE_UPDATE_INTERVAL {
+            window.set_title(&format!(
+                "{} - {} | gpu: {}",
+                WINDOW_TITLE,
+                frame_timer.stats(),
+                render_ctx.gpu_profile
+            ));
+            last_title_update = Instant::now();
+
+            if let Some(pipeline_statistics) = render_ctx.pipeline_statistics() {
+                println!(
+                    "pipeline statistics of the last frame:\n{}",
+                    pipeline_statistics
+                );
+            }
+        }
+
+        #[cfg(feature = "hot-reload")]
+        if shader_watcher.poll() {
+            let result =
+                render_ctx.reload_pipeline(|name| shader_watcher.compile(name).map(Cow::Owned));
+            match result {
+                Ok(()) => log::info!("Reloaded shaders"),
+                Err(e) => log::warn!(
+                    "Failed to reload shaders, keeping the previous pipelines:\n{:#}",
+                    e
+                ),
+            }
+        }
+
+        if mem::take(&mut resized) {
+            render_ctx.swapchain_out_of_date = true;
+        }
+
+        //a minimized window has no area, nothing is recreated or drawn until it is restored
+        let window_size = window.inner_size();
+        if window_size.width == 0 || window_size.height == 0 {
+            continue;
+        }
+        if render_ctx.swapchain_out_of_date {
+            if let Err(e) = render_ctx.recreate_swapchain(window_size.width, window_size.height) {
+                log::error!("Failed to recreate the swapchain: {:#}", e);
+                failed = true;
+                break;
+            }
+            //the surface can still report a zero extent while the window size is not yet updated
+            if render_ctx.swapchain_out_of_date {
+                continue;
+            }
+        }
+
+        camera.set_viewport_size(
+            render_ctx.swapchain_extent.width,
+            render_ctx.swapchain_extent.height,
+        );
+        for _ in 0..simulation.accumulate(frame_timer.delta()) {
+            camera.move_with_keys(&pressed_keys, simulation.step().as_secs_f32());
+        }
+        camera.update_view_projection_matrix();
+        if let Err(e) =
+            unsafe { renderer::render_frame(&mut render_ctx, &mut frame_index, &camera) }
+        {
+            log::error!("Failed to render a frame: {:#}", e);
+            failed = true;
+            break;
         }
 
         frame_count += 1;
         frame_index = frame_count % render_ctx.frames.len();
     }
+
+    if let Some(path) = gpu_trace_output {
+        let file = File::create(&path).unwrap();
+        render_ctx
+            .gpu_profile
+            .write_chrome_trace(BufWriter::new(file))
+            .unwrap();
+    }
+
+    if failed {
+        //exit skips destructors
+        drop(render_ctx);
+        process::exit(1);
+    }
+}
+
+//removes name from the arguments and returns whether it was present
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let index = args.iter().position(|arg| arg == name);
+    if let Some(index) = index {
+        args.remove(index);
+    }
+    index.is_some()
+}
+
+//removes "<name> <value>" from the arguments
+fn take_option(args: &mut Vec<String>, name: &str) -> anyhow::Result<Option<String>> {
+    let index = match args.iter().position(|arg| arg == name) {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+    args.remove(index);
+    if index == args.len() {
+        bail!("{} requires a value", name);
+    }
+    Ok(Some(args.remove(index)))
+}
+
+//like take_option, but parses the value
+fn take_parsed_option<T>(args: &mut Vec<String>, name: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    take_option(args, name)?
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|e| anyhow!("Invalid value {:?} for {}: {}", value, name, e))
+        })
+        .transpose()
+}
+
+fn parse_args(mut args: Vec<String>) -> anyhow::Result<Args> {
+    let headless_output = take_option(&mut args, "--headless")?;
+    let gpu_trace_output = take_option(&mut args, "--gpu-trace")?;
+    let print_pipeline_statistics = take_flag(&mut args, "--pipeline-statistics");
+
+    let mut config = RenderConfig::from_env();
+    config.validation |= take_flag(&mut args, "--validation");
+    if let Some(present_mode) = take_parsed_option(&mut args, "--present-mode")? {
+        config.present_mode = present_mode;
+    }
+    if let Some(frames_in_flight) = take_parsed_option(&mut args, "--frames-in-flight")? {
+        if frames_in_flight == 0 {
+            bail!("--frames-in-flight must be at least 1");
+        }
+        config.frames_in_flight = frames_in_flight;
+    }
+    if let Some(hdr_output) = take_parsed_option(&mut args, "--hdr")? {
+        config.hdr_output = Some(hdr_output);
+    }
+    if let Some(paper_white_nits) = take_parsed_option::<f32>(&mut args, "--paper-white")? {
+        if !(paper_white_nits.is_finite() && paper_white_nits > 0.0) {
+            bail!("--paper-white must be a positive number of nits");
+        }
+        config.paper_white_nits = paper_white_nits;
+    }
+    if let Some(device) = take_parsed_option(&mut args, "--device")? {
+        config.device = Some(device);
+    }
+    let frame_limiter = take_parsed_option(&mut args, "--max-fps")?
+        .map(FrameLimiter::new)
+        .transpose()?;
+
+    if let Some(arg) = args.iter().find(|arg| arg.starts_with("--")) {
+        bail!("Unknown option {}", arg);
+    }
+    if args.len() > 1 {
+        bail!("Expected at most one mesh, got {}", args.join(" "));
+    }
+
+    Ok(Args {
+        headless_output,
+        gpu_trace_output,
+        print_pipeline_statistics,
+        config,
+        frame_limiter,
+        mesh_path: args.pop(),
+    })
+}
+
+fn render_headless(scene: &Scene, output: &str, config: &RenderConfig) -> anyhow::Result<()> {
+    let mut render_ctx = RenderCtx::new_headless(HEADLESS_WIDTH, HEADLESS_HEIGHT, config)?;
+    render_ctx.set_scene(scene)?;
+
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 0.0));
+    camera.set_viewport_size(HEADLESS_WIDTH, HEADLESS_HEIGHT);
+    camera.update(&HashSet::new(), 0.0);
+
+    let pixels = unsafe { renderer::render_offscreen(&render_ctx, &camera)? };
+    Image::new(HEADLESS_WIDTH, HEADLESS_HEIGHT, pixels)?.save(output)
+}
+
+//obj files and the default triangle become a scene with a single node
+fn load_scene(path: Option<&String>) -> anyhow::Result<Scene> {
+    match path {
+        Some(path) if path.ends_with(".gltf") || path.ends_with(".glb") => Scene::load(&path),
+        Some(path) => Ok(Scene::from_mesh(obj::load(&path)?)),
+        None => Ok(Scene::from_mesh(Mesh::triangle())),
+    }
 }
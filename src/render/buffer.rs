@@ -31,6 +31,53 @@ impl Buffer {
             allocator,
         })
     }
+
+    //host visible and cached, for reading back results written by the gpu
+    pub fn new_gpu_to_cpu(
+        allocator: Arc<Allocator>,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+    ) -> Result<Self> {
+        let buffer_create_info = vk::BufferCreateInfo::default().size(size).usage(usage);
+        let allocation_create_info = AllocationCreateInfo::new().usage(MemoryUsage::GpuToCpu);
+        let buffer =
+            unsafe { allocator.create_buffer(&buffer_create_info, &allocation_create_info)? };
+        let memory = Some(unsafe { allocator.map_memory(buffer.1)? });
+        Ok(Self {
+            buffer: buffer.0,
+            size,
+            allocation: buffer.1,
+            memory,
+            allocator,
+        })
+    }
+
+    pub fn new_gpu_only(
+        allocator: Arc<Allocator>,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+    ) -> Result<Self> {
+        let buffer_create_info = vk::BufferCreateInfo::default().size(size).usage(usage);
+        let allocation_create_info = AllocationCreateInfo::new().usage(MemoryUsage::GpuOnly);
+        let buffer =
+            unsafe { allocator.create_buffer(&buffer_create_info, &allocation_create_info)? };
+        Ok(Self {
+            buffer: buffer.0,
+            size,
+            allocation: buffer.1,
+            memory: None,
+            allocator,
+        })
+    }
+
+    //makes gpu writes visible to the mapped pointer, needed if the memory is not host coherent
+    pub fn invalidate(&self) -> Result<()> {
+        unsafe {
+            self.allocator
+                .invalidate_allocation(self.allocation, 0, self.size)?
+        };
+        Ok(())
+    }
 }
 
 impl Drop for Buffer {
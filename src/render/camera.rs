@@ -8,11 +8,21 @@ pub const SENSITIVITY_X: f32 = 0.165f32;
 pub const SENSITIVITY_Y: f32 = 0.165f32;
 pub const SPEED: f32 = 12.5f32;
 
+pub const DEFAULT_FOV_Y: f32 = 90f32;
+pub const DEFAULT_NEAR: f32 = 0.1f32;
+pub const DEFAULT_FAR: f32 = 10000f32;
+
 #[derive(Debug)]
 pub struct Camera {
     pub position: Vec3,
     pub rotation: Vec3,
 
+    //vertical field of view in degrees
+    pub fov_y: f32,
+    pub aspect_ratio: f32,
+    pub near: f32,
+    pub far: f32,
+
     pub view_projection_matrix: Mat4,
 }
 
@@ -21,22 +31,44 @@ impl Camera {
         Self {
             position,
             rotation,
+
+            fov_y: DEFAULT_FOV_Y,
+            aspect_ratio: 16f32 / 9f32,
+            near: DEFAULT_NEAR,
+            far: DEFAULT_FAR,
+
             view_projection_matrix: Mat4::default(),
         }
     }
 
+    pub fn set_viewport_size(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.aspect_ratio = width as f32 / height as f32;
+        }
+    }
+
     pub fn update(&mut self, pressed_keys: &HashSet<VirtualKeyCode>, delta: f32) {
+        self.move_with_keys(pressed_keys, delta);
+        self.update_view_projection_matrix();
+    }
+
+    pub fn update_view_projection_matrix(&mut self) {
         let mut invert_y_matrix = Mat4::default();
         invert_y_matrix.col_mut(1).y = -1.0f32;
 
-        let projection_matrix =
-            Mat4::perspective_lh(90f32.to_radians(), 16f32 / 9f32, 0.1f32, 10000f32)
-                * invert_y_matrix;
+        let projection_matrix = Mat4::perspective_lh(
+            self.fov_y.to_radians(),
+            self.aspect_ratio,
+            self.near,
+            self.far,
+        ) * invert_y_matrix;
 
         let look_at = self.position + direction_from_rotation(&self.rotation);
         self.view_projection_matrix = projection_matrix
             * Mat4::look_at_lh(self.position, look_at, Vec3::new(0f32, 1f32, 0f32));
+    }
 
+    pub fn move_with_keys(&mut self, pressed_keys: &HashSet<VirtualKeyCode>, delta: f32) {
         if pressed_keys.contains(&VirtualKeyCode::W) {
             self.move_forward(delta);
         }
@@ -0,0 +1,28 @@
+//destroys whatever a constructor created so far if a later step fails, in reverse order of creation
+#[derive(Default)]
+pub struct Cleanup {
+    actions: Vec<Box<dyn FnOnce()>>,
+}
+
+impl Cleanup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, action: impl FnOnce() + 'static) {
+        self.actions.push(Box::new(action));
+    }
+
+    //construction succeeded, the finished object is responsible for destruction now
+    pub fn dismiss(mut self) {
+        self.actions.clear();
+    }
+}
+
+impl Drop for Cleanup {
+    fn drop(&mut self) {
+        while let Some(action) = self.actions.pop() {
+            action();
+        }
+    }
+}
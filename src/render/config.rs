@@ -0,0 +1,183 @@
+use std::{env, fmt, path::PathBuf, str::FromStr};
+
+use anyhow::{bail, Result};
+use ash::vk;
+
+use crate::render::device_selection::DevicePreference;
+
+//"1" or "0", overrides whether validation is requested
+pub const VALIDATION_ENV_VAR: &str = "VULKAN_EXPERIMENTS_VALIDATION";
+//a device index or name, see DevicePreference, used unless a device is passed on the command line
+pub const DEVICE_ENV_VAR: &str = "VULKAN_EXPERIMENTS_DEVICE";
+
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+//brightness of scene color 1.0 on hdr displays
+pub const DEFAULT_PAPER_WHITE_NITS: f32 = 200.0;
+
+#[derive(Clone, Debug)]
+pub struct RenderConfig {
+    //requests VK_LAYER_KHRONOS_validation and a debug messenger, ignored with a warning if the layer is not installed
+    pub validation: bool,
+    //validation errors panic on the next frame instead of only being logged, meant for tests
+    pub panic_on_validation_error: bool,
+    //falls back to a supported mode, see choose_present_mode
+    pub present_mode: PresentMode,
+    //frames the cpu may record ahead of the gpu, at least 1
+    pub frames_in_flight: usize,
+    //None for sdr, falls back to sdr if the surface does not support the color space
+    pub hdr_output: Option<HdrOutput>,
+    pub paper_white_nits: f32,
+    //where the spir-v is read from with the shaders-from-disk feature, empty for the working directory
+    pub shader_directory: PathBuf,
+    //None selects the most capable device
+    pub device: Option<DevicePreference>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            validation: false,
+            panic_on_validation_error: false,
+            present_mode: PresentMode::Fifo,
+            frames_in_flight: DEFAULT_FRAMES_IN_FLIGHT,
+            hdr_output: None,
+            paper_white_nits: DEFAULT_PAPER_WHITE_NITS,
+            shader_directory: PathBuf::new(),
+            device: None,
+        }
+    }
+}
+
+impl RenderConfig {
+    //validation is on in debug builds unless the environment says otherwise
+    pub fn from_env() -> Self {
+        let device = env::var(DEVICE_ENV_VAR)
+            .ok()
+            .map(|value| value.parse().unwrap_or_else(|e| match e {}));
+
+        let validation = match env::var(VALIDATION_ENV_VAR).as_deref() {
+            Ok("1") => true,
+            Ok("0") => false,
+            _ => cfg!(debug_assertions),
+        };
+
+        Self {
+            validation,
+            device,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentMode {
+    //vsync, always supported
+    Fifo,
+    //vsync, but late frames are presented immediately and may tear
+    FifoRelaxed,
+    //no tearing, the newest frame replaces a queued one, so rendering is uncapped
+    Mailbox,
+    //uncapped and may tear
+    Immediate,
+}
+
+impl PresentMode {
+    pub fn to_vk(self) -> vk::PresentModeKHR {
+        match self {
+            Self::Fifo => vk::PresentModeKHR::FIFO,
+            Self::FifoRelaxed => vk::PresentModeKHR::FIFO_RELAXED,
+            Self::Mailbox => vk::PresentModeKHR::MAILBOX,
+            Self::Immediate => vk::PresentModeKHR::IMMEDIATE,
+        }
+    }
+}
+
+impl FromStr for PresentMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "fifo" => Self::Fifo,
+            "fifo-relaxed" => Self::FifoRelaxed,
+            "mailbox" => Self::Mailbox,
+            "immediate" => Self::Immediate,
+            _ => bail!(
+                "Unknown present mode {}, expected fifo, fifo-relaxed, mailbox or immediate",
+                s
+            ),
+        })
+    }
+}
+
+impl fmt::Display for PresentMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Fifo => "fifo",
+            Self::FifoRelaxed => "fifo-relaxed",
+            Self::Mailbox => "mailbox",
+            Self::Immediate => "immediate",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HdrOutput {
+    //rec. 2020 primaries with the pq transfer function in a 10 bit format
+    Hdr10,
+    //linear extended srgb in a float format, 1.0 is 80 nits
+    ScRgb,
+}
+
+impl FromStr for HdrOutput {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "hdr10" => Self::Hdr10,
+            "scrgb" => Self::ScRgb,
+            _ => bail!("Unknown hdr output {}, expected hdr10 or scrgb", s),
+        })
+    }
+}
+
+impl fmt::Display for HdrOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Hdr10 => "hdr10",
+            Self::ScRgb => "scrgb",
+        })
+    }
+}
+
+//the uncapped modes fall back to each other before settling for FIFO, which every surface supports
+pub fn choose_present_mode(
+    preferred: PresentMode,
+    supported: &[vk::PresentModeKHR],
+) -> vk::PresentModeKHR {
+    let fallbacks: &[PresentMode] = match preferred {
+        PresentMode::Fifo => &[],
+        PresentMode::FifoRelaxed => &[PresentMode::FifoRelaxed],
+        PresentMode::Mailbox => &[PresentMode::Mailbox, PresentMode::Immediate],
+        PresentMode::Immediate => &[PresentMode::Immediate, PresentMode::Mailbox],
+    };
+
+    fallbacks
+        .iter()
+        .map(|present_mode| present_mode.to_vk())
+        .find(|present_mode| supported.contains(present_mode))
+        .unwrap_or(vk::PresentModeKHR::FIFO)
+}
+
+//the presentation engine does not pace rendering in these modes, see timing::FrameLimiter
+pub fn is_uncapped(present_mode: vk::PresentModeKHR) -> bool {
+    present_mode == vk::PresentModeKHR::MAILBOX || present_mode == vk::PresentModeKHR::IMMEDIATE
+}
+
+//one image more than the minimum, so acquiring never has to wait for the presentation engine to release one
+pub fn choose_image_count(surface_capabilities: &vk::SurfaceCapabilitiesKHR) -> u32 {
+    let image_count = surface_capabilities.min_image_count + 1;
+    match surface_capabilities.max_image_count {
+        0 => image_count,
+        max_image_count => image_count.min(max_image_count),
+    }
+}
@@ -0,0 +1,78 @@
+use glam::{Mat4, Vec3, Vec4};
+
+use crate::render::meshlet::MeshletBounds;
+
+//the same tests are performed in example.task.glsl and example.ext.task.glsl, keep them in sync
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Frustum {
+    //left, right, bottom, top, near, far; normals point inwards and are normalized
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    //vulkan clip space, -w <= x, y <= w and 0 <= z <= w
+    pub fn from_matrix(view_projection_matrix: &Mat4) -> Self {
+        let m = view_projection_matrix.transpose();
+        let (row0, row1, row2, row3) = (m.x_axis, m.y_axis, m.z_axis, m.w_axis);
+
+        let planes = [
+            row3 + row0,
+            row3 - row0,
+            row3 + row1,
+            row3 - row1,
+            row2,
+            row3 - row2,
+        ]
+        .map(|plane| plane / plane.truncate().length());
+
+        Self { planes }
+    }
+
+    pub fn is_sphere_visible(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+    }
+}
+
+pub fn is_cone_back_facing(bounds: &MeshletBounds, camera_position: Vec3) -> bool {
+    let direction = bounds.center - camera_position;
+
+    direction.dot(bounds.cone_axis) >= bounds.cone_cutoff * direction.length() + bounds.radius
+}
+
+pub fn is_meshlet_visible(
+    bounds: &MeshletBounds,
+    frustum: &Frustum,
+    camera_position: Vec3,
+) -> bool {
+    frustum.is_sphere_visible(bounds.center, bounds.radius)
+        && !is_cone_back_facing(bounds, camera_position)
+}
+
+//the bounds of a meshlet drawn with transform, the cone is dropped if transform does not preserve angles
+pub fn transform_bounds(bounds: &MeshletBounds, transform: &Mat4) -> MeshletBounds {
+    let scales =
+        [transform.x_axis, transform.y_axis, transform.z_axis].map(|axis| axis.truncate().length());
+    let max_scale = scales[0].max(scales[1]).max(scales[2]);
+    let min_scale = scales[0].min(scales[1]).min(scales[2]);
+
+    let center = transform.transform_point3(bounds.center);
+    let radius = bounds.radius * max_scale;
+
+    if max_scale - min_scale > max_scale * 1e-3 || bounds.cone_cutoff >= 1.0 {
+        return MeshletBounds {
+            center,
+            radius,
+            cone_axis: Vec3::ZERO,
+            cone_cutoff: 1.0,
+        };
+    }
+
+    MeshletBounds {
+        center,
+        radius,
+        cone_axis: transform.transform_vector3(bounds.cone_axis).normalize(),
+        cone_cutoff: bounds.cone_cutoff,
+    }
+}
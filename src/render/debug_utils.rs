@@ -0,0 +1,173 @@
+use std::{
+    borrow::Cow,
+    ffi::{c_void, CStr, CString},
+    sync::Mutex,
+};
+
+use ash::{extensions::ext::DebugUtils, vk, Entry, Instance};
+
+pub const VALIDATION_LAYER: &[u8] = b"VK_LAYER_KHRONOS_validation\0";
+
+//all messages are logged with this target, so they can be filtered separately, e.g. RUST_LOG=vulkan=warn
+pub const LOG_TARGET: &str = "vulkan";
+
+//boxed, so the pointer handed to the driver as user data stays valid
+struct MessengerState {
+    panic_on_error: bool,
+    errors: Mutex<Vec<String>>,
+}
+
+//routes validation layer and driver messages into the log crate
+pub struct DebugMessenger {
+    pub debug_utils_loader: DebugUtils,
+    messenger: vk::DebugUtilsMessengerEXT,
+    state: Box<MessengerState>,
+}
+
+impl DebugMessenger {
+    pub unsafe fn new(
+        entry: &Entry,
+        instance: &Instance,
+        panic_on_error: bool,
+    ) -> Result<Self, vk::Result> {
+        let debug_utils_loader = DebugUtils::new(entry, instance);
+        let state = Box::new(MessengerState {
+            panic_on_error,
+            errors: Mutex::new(Vec::new()),
+        });
+
+        let messenger_create_info = vk::DebugUtilsMessengerCreateInfoEXT::default()
+            .message_severity(
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::INFO
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            )
+            .message_type(
+                vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                    | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                    | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+            )
+            .pfn_user_callback(Some(debug_callback))
+            .user_data(&*state as *const MessengerState as *mut c_void);
+
+        let messenger =
+            debug_utils_loader.create_debug_utils_messenger(&messenger_create_info, None)?;
+
+        Ok(Self {
+            debug_utils_loader,
+            messenger,
+            state,
+        })
+    }
+
+    //panics with every error reported since the last call if panic_on_error was set, does nothing otherwise
+    pub fn check(&self) {
+        let errors = std::mem::take(&mut *self.state.errors.lock().unwrap());
+        if !errors.is_empty() {
+            panic!("Vulkan validation failed:\n{}", errors.join("\n"));
+        }
+    }
+}
+
+impl Drop for DebugMessenger {
+    fn drop(&mut self) {
+        unsafe {
+            self.debug_utils_loader
+                .destroy_debug_utils_messenger(self.messenger, None);
+        }
+    }
+}
+
+//panicking here would unwind into the driver, errors are collected for check instead
+unsafe extern "system" fn debug_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+    user_data: *mut c_void,
+) -> vk::Bool32 {
+    let callback_data = &*callback_data;
+    let message = to_string(callback_data.p_message);
+    let message_id_name = to_string(callback_data.p_message_id_name);
+
+    let level = match message_severity {
+        vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => log::Level::Error,
+        vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => log::Level::Warn,
+        vk::DebugUtilsMessageSeverityFlagsEXT::INFO => log::Level::Debug,
+        _ => log::Level::Trace,
+    };
+    log::log!(target: LOG_TARGET, level, "{:?} [{}] {}", message_type, message_id_name, message);
+
+    let state = &*(user_data as *const MessengerState);
+    if level == log::Level::Error && state.panic_on_error {
+        if let Ok(mut errors) = state.errors.lock() {
+            errors.push(format!("[{}] {}", message_id_name, message));
+        }
+    }
+
+    //the call that triggered the message must not be aborted
+    vk::FALSE
+}
+
+unsafe fn to_string(ptr: *const std::os::raw::c_char) -> Cow<'static, str> {
+    if ptr.is_null() {
+        Cow::Borrowed("")
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy()
+    }
+}
+
+//names objects and labels command buffers for capture tools, every call does nothing without VK_EXT_debug_utils
+#[derive(Clone)]
+pub struct DebugNames {
+    debug_utils_loader: Option<DebugUtils>,
+    device: vk::Device,
+}
+
+impl DebugNames {
+    pub fn new(debug_utils_loader: Option<DebugUtils>, device: vk::Device) -> Self {
+        Self {
+            debug_utils_loader,
+            device,
+        }
+    }
+
+    //a failure only costs the name, so it is logged instead of returned
+    pub unsafe fn set_object_name<T: vk::Handle>(&self, handle: T, name: &str) {
+        let debug_utils_loader = match &self.debug_utils_loader {
+            Some(debug_utils_loader) => debug_utils_loader,
+            None => return,
+        };
+
+        let name = to_c_string(name);
+        let mut name_info = vk::DebugUtilsObjectNameInfoEXT::default().object_name(&name);
+        name_info.object_type = T::TYPE;
+        name_info.object_handle = handle.as_raw();
+
+        if let Err(result) = debug_utils_loader.set_debug_utils_object_name(self.device, &name_info)
+        {
+            log::warn!(target: LOG_TARGET, "Failed to name {:?} {:?}: {}", T::TYPE, name, result);
+        }
+    }
+
+    //must be closed with end_label in the same command buffer, labels nest
+    pub unsafe fn begin_label(&self, command_buffer: vk::CommandBuffer, name: &str) {
+        if let Some(debug_utils_loader) = &self.debug_utils_loader {
+            let name = to_c_string(name);
+            let label = vk::DebugUtilsLabelEXT::default().label_name(&name);
+            debug_utils_loader.cmd_begin_debug_utils_label(command_buffer, &label);
+        }
+    }
+
+    pub unsafe fn end_label(&self, command_buffer: vk::CommandBuffer) {
+        if let Some(debug_utils_loader) = &self.debug_utils_loader {
+            debug_utils_loader.cmd_end_debug_utils_label(command_buffer);
+        }
+    }
+}
+
+//names are only ever written by this crate, an interior nul is cut off instead of failing
+fn to_c_string(name: &str) -> CString {
+    let name = name.split('\0').next().unwrap_or_default();
+    CString::new(name).unwrap()
+}
@@ -0,0 +1,276 @@
+use std::{
+    convert::Infallible,
+    ffi::CStr,
+    fmt::{self, Write},
+    str::FromStr,
+};
+
+use anyhow::{bail, Result};
+use ash::{extensions::khr::Surface, vk, Instance};
+
+use crate::render::mesh_shader::MeshShaderVariant;
+
+pub struct SelectedDevice {
+    pub physical_device: vk::PhysicalDevice,
+    pub properties: vk::PhysicalDeviceProperties,
+    pub queue_family_index: u32,
+    //None if neither mesh shader extension is usable, the vertex shader fallback is used then
+    pub mesh_shader_variant: Option<MeshShaderVariant>,
+    pub optional_features: OptionalFeatures,
+}
+
+//enabled when supported, but not required for a device to be selected
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OptionalFeatures {
+    pub pipeline_statistics_query: bool,
+    //task and mesh shader invocation counters, only exist for VK_EXT_mesh_shader
+    pub mesh_shader_queries: bool,
+}
+
+//either a device index as listed by enumerate_physical_devices or a case insensitive substring of its name
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DevicePreference {
+    Index(usize),
+    //lowercase
+    Name(String),
+}
+
+impl FromStr for DevicePreference {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Infallible> {
+        Ok(match s.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(s.to_lowercase()),
+        })
+    }
+}
+
+impl fmt::Display for DevicePreference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "device {}", index),
+            Self::Name(name) => write!(f, "device name {:?}", name),
+        }
+    }
+}
+
+impl DevicePreference {
+    fn matches(&self, index: usize, properties: &vk::PhysicalDeviceProperties) -> bool {
+        match self {
+            Self::Index(preferred_index) => *preferred_index == index,
+            Self::Name(name) => device_name(properties).to_lowercase().contains(name),
+        }
+    }
+}
+
+//without a surface any graphics queue family is accepted, which is what headless rendering needs
+//with a preference only the devices it matches are considered
+pub unsafe fn select_physical_device(
+    instance: &Instance,
+    surface_loader: &Surface,
+    surface: Option<vk::SurfaceKHR>,
+    required_extensions: &[&CStr],
+    preference: Option<&DevicePreference>,
+) -> Result<SelectedDevice> {
+    let mut best: Option<(u32, SelectedDevice)> = None;
+    let mut rejections = String::new();
+
+    for (index, physical_device) in instance
+        .enumerate_physical_devices()?
+        .into_iter()
+        .enumerate()
+    {
+        let properties = instance.get_physical_device_properties(physical_device);
+
+        if let Some(preference) = preference {
+            if !preference.matches(index, &properties) {
+                writeln!(
+                    rejections,
+                    "  [{}] {}: does not match the requested {}",
+                    index,
+                    device_name(&properties),
+                    preference
+                )?;
+                continue;
+            }
+        }
+
+        match check_device(
+            instance,
+            surface_loader,
+            surface,
+            physical_device,
+            &properties,
+            required_extensions,
+        ) {
+            Ok((queue_family_index, mesh_shader_variant, optional_features)) => {
+                //the device type matters most, mesh shader support breaks ties
+                let score = score_device(&properties) * 2 + mesh_shader_variant.is_some() as u32;
+                if best
+                    .as_ref()
+                    .map_or(true, |(best_score, _)| score > *best_score)
+                {
+                    best = Some((
+                        score,
+                        SelectedDevice {
+                            physical_device,
+                            properties,
+                            queue_family_index,
+                            mesh_shader_variant,
+                            optional_features,
+                        },
+                    ));
+                }
+            }
+            Err(reasons) => writeln!(
+                rejections,
+                "  [{}] {}: {}",
+                index,
+                device_name(&properties),
+                reasons.join(", ")
+            )?,
+        }
+    }
+
+    match best {
+        Some((_, selected_device)) => Ok(selected_device),
+        None if rejections.is_empty() => bail!("No Vulkan physical devices found"),
+        None => bail!(
+            "No suitable physical device found:\n{}",
+            rejections.trim_end()
+        ),
+    }
+}
+
+//collects every reason a device is unsuitable instead of stopping at the first one
+unsafe fn check_device(
+    instance: &Instance,
+    surface_loader: &Surface,
+    surface: Option<vk::SurfaceKHR>,
+    physical_device: vk::PhysicalDevice,
+    properties: &vk::PhysicalDeviceProperties,
+    required_extensions: &[&CStr],
+) -> Result<(u32, Option<MeshShaderVariant>, OptionalFeatures), Vec<String>> {
+    let mut reasons = Vec::new();
+
+    if properties.api_version < vk::API_VERSION_1_3 {
+        reasons.push(format!(
+            "Vulkan {}.{} is below the required 1.3",
+            vk::api_version_major(properties.api_version),
+            vk::api_version_minor(properties.api_version)
+        ));
+    }
+
+    let extension_properties = match instance.enumerate_device_extension_properties(physical_device)
+    {
+        Ok(extension_properties) => extension_properties,
+        Err(result) => return Err(vec![format!("failed to enumerate extensions: {}", result)]),
+    };
+    let is_extension_supported = |extension: &CStr| {
+        extension_properties
+            .iter()
+            .any(|properties| CStr::from_ptr(properties.extension_name.as_ptr()) == extension)
+    };
+
+    for required_extension in required_extensions {
+        if !is_extension_supported(required_extension) {
+            reasons.push(format!(
+                "missing extension {}",
+                required_extension.to_string_lossy()
+            ));
+        }
+    }
+
+    let mut mesh_shader_features_ext = vk::PhysicalDeviceMeshShaderFeaturesEXT::default();
+    let mut mesh_shader_features_nv = vk::PhysicalDeviceMeshShaderFeaturesNV::default();
+    let mut vulkan_13_features = vk::PhysicalDeviceVulkan13Features::default();
+    let mut features = vk::PhysicalDeviceFeatures2::default().push_next(&mut vulkan_13_features);
+    //structures of unsupported extensions must not be chained
+    if is_extension_supported(MeshShaderVariant::Ext.extension_name()) {
+        features = features.push_next(&mut mesh_shader_features_ext);
+    }
+    if is_extension_supported(MeshShaderVariant::Nv.extension_name()) {
+        features = features.push_next(&mut mesh_shader_features_nv);
+    }
+    instance.get_physical_device_features2(physical_device, &mut features);
+    let pipeline_statistics_query = features.features.pipeline_statistics_query == vk::TRUE;
+
+    let required_features = [
+        ("dynamicRendering", vulkan_13_features.dynamic_rendering),
+        ("synchronization2", vulkan_13_features.synchronization2),
+    ];
+    for (name, supported) in required_features {
+        if supported == vk::FALSE {
+            reasons.push(format!("missing feature {}", name));
+        }
+    }
+
+    let mesh_shader_variant = MeshShaderVariant::ALL.into_iter().find(|variant| {
+        let (task_shader, mesh_shader) = match variant {
+            MeshShaderVariant::Ext => (
+                mesh_shader_features_ext.task_shader,
+                mesh_shader_features_ext.mesh_shader,
+            ),
+            MeshShaderVariant::Nv => (
+                mesh_shader_features_nv.task_shader,
+                mesh_shader_features_nv.mesh_shader,
+            ),
+        };
+
+        is_extension_supported(variant.extension_name())
+            && task_shader == vk::TRUE
+            && mesh_shader == vk::TRUE
+    });
+    let optional_features = OptionalFeatures {
+        pipeline_statistics_query,
+        mesh_shader_queries: mesh_shader_variant == Some(MeshShaderVariant::Ext)
+            && mesh_shader_features_ext.mesh_shader_queries == vk::TRUE,
+    };
+
+    let queue_family_index = instance
+        .get_physical_device_queue_family_properties(physical_device)
+        .iter()
+        .enumerate()
+        .position(|(index, queue_family_properties)| {
+            queue_family_properties
+                .queue_flags
+                .contains(vk::QueueFlags::GRAPHICS)
+                && surface.map_or(true, |surface| {
+                    surface_loader
+                        .get_physical_device_surface_support(physical_device, index as u32, surface)
+                        .unwrap_or(false)
+                })
+        });
+    if queue_family_index.is_none() {
+        reasons.push(String::from(match surface {
+            Some(_) => "no queue family supports both graphics and presentation",
+            None => "no queue family supports graphics",
+        }));
+    }
+
+    match queue_family_index {
+        Some(queue_family_index) if reasons.is_empty() => Ok((
+            queue_family_index as u32,
+            mesh_shader_variant,
+            optional_features,
+        )),
+        _ => Err(reasons),
+    }
+}
+
+fn score_device(properties: &vk::PhysicalDeviceProperties) -> u32 {
+    match properties.device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => 4,
+        vk::PhysicalDeviceType::INTEGRATED_GPU => 3,
+        vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
+        vk::PhysicalDeviceType::CPU => 1,
+        _ => 0,
+    }
+}
+
+fn device_name(properties: &vk::PhysicalDeviceProperties) -> String {
+    unsafe { CStr::from_ptr(properties.device_name.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,93 @@
+use std::{error::Error, fmt};
+
+use ash::{vk, LoadingError};
+
+//why a RenderCtx or one of its frames could not be created, what is a verb phrase like "create the instance"
+#[derive(Debug)]
+pub enum RenderCtxError {
+    //a RenderConfig value that can not be used, checked before anything is created
+    InvalidConfig(&'static str),
+    //the vulkan library itself could not be loaded, e.g. no driver is installed
+    Loader(LoadingError),
+    MissingInstanceLayer(String),
+    MissingInstanceExtension(String),
+    //no device supports everything that is required, lists why each one was rejected
+    MissingDeviceFeature(String),
+    Surface {
+        what: &'static str,
+        result: vk::Result,
+    },
+    OutOfMemory {
+        what: &'static str,
+        result: vk::Result,
+    },
+    Vulkan {
+        what: &'static str,
+        result: vk::Result,
+    },
+    //failures that are not a vulkan error code, e.g. shaders that fail reflection
+    Other {
+        what: &'static str,
+        error: anyhow::Error,
+    },
+}
+
+impl RenderCtxError {
+    //for map_err on vulkan calls, out of memory results are told apart from other failures
+    pub fn vulkan(what: &'static str) -> impl FnOnce(vk::Result) -> Self {
+        move |result| match result {
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY
+            | vk::Result::ERROR_OUT_OF_DEVICE_MEMORY
+            | vk::Result::ERROR_OUT_OF_POOL_MEMORY
+            | vk::Result::ERROR_FRAGMENTED_POOL => Self::OutOfMemory { what, result },
+            vk::Result::ERROR_SURFACE_LOST_KHR | vk::Result::ERROR_NATIVE_WINDOW_IN_USE_KHR => {
+                Self::Surface { what, result }
+            }
+            _ => Self::Vulkan { what, result },
+        }
+    }
+
+    pub fn surface(what: &'static str) -> impl FnOnce(vk::Result) -> Self {
+        move |result| match result {
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY | vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => {
+                Self::OutOfMemory { what, result }
+            }
+            _ => Self::Surface { what, result },
+        }
+    }
+
+    //for map_err on the anyhow based helpers, which pass vulkan errors through unchanged
+    pub fn other(what: &'static str) -> impl FnOnce(anyhow::Error) -> Self {
+        move |error| match error.downcast_ref::<vk::Result>() {
+            Some(&result) => Self::vulkan(what)(result),
+            None => Self::Other { what, error },
+        }
+    }
+}
+
+impl fmt::Display for RenderCtxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidConfig(reason) => write!(f, "Invalid render configuration: {}", reason),
+            Self::Loader(e) => write!(f, "Failed to load the Vulkan library: {}", e),
+            Self::MissingInstanceLayer(layer) => {
+                write!(f, "The instance layer {} is not available", layer)
+            }
+            Self::MissingInstanceExtension(extension) => {
+                write!(f, "The instance extension {} is not available", extension)
+            }
+            Self::MissingDeviceFeature(reasons) => write!(f, "{}", reasons),
+            Self::Surface { what, result } => {
+                write!(f, "Failed to {} for the surface: {}", what, result)
+            }
+            Self::OutOfMemory { what, result } => {
+                write!(f, "Out of memory trying to {}: {}", what, result)
+            }
+            Self::Vulkan { what, result } => write!(f, "Failed to {}: {}", what, result),
+            Self::Other { what, error } => write!(f, "Failed to {}: {:#}", what, error),
+        }
+    }
+}
+
+//the vulkan result is part of the message, so there is no separate source
+impl Error for RenderCtxError {}
@@ -1,9 +1,13 @@
 use std::{mem, slice, sync::Arc};
 
-use crate::render::Buffer;
+use crate::render::{
+    debug_utils::DebugNames, error::RenderCtxError, geometry::Geometry,
+    pipeline_statistics::PipelineStatisticsQuery, profiler::GpuProfiler,
+    reflection::PipelineReflection, Buffer,
+};
 use ash::vk::{DescriptorBufferInfo, DescriptorPoolSize};
 use ash::{vk, Device};
-use glam::Mat4;
+use glam::{Mat4, Vec3, Vec4};
 use vk_mem::Allocator;
 
 pub struct Frame {
@@ -19,103 +23,191 @@ pub struct Frame {
     pub descriptor_pool: vk::DescriptorPool,
     pub descriptor_set: vk::DescriptorSet,
 
+    pub profiler: GpuProfiler,
+    //None if pipeline statistics are unsupported
+    pub pipeline_statistics_query: Option<PipelineStatisticsQuery>,
+
     device: Arc<Device>,
 }
 
-pub const NUM_FRAMES: usize = 2;
+//laid out to match the std140 uniform block in the task and mesh shaders
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct FrameUniform {
+    pub view_projection_matrix: Mat4,
+    pub frustum_planes: [Vec4; 6],
+    pub camera_position: Vec3,
+}
 
 impl Frame {
+    //index is only used to name the objects of the frame
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
+        index: usize,
+        debug_names: &DebugNames,
         device: Arc<Device>,
         allocator: Arc<Allocator>,
+        queue_family_index: u32,
         descriptor_set_layout: vk::DescriptorSetLayout,
-    ) -> Self {
+        descriptor_pool_sizes: &[DescriptorPoolSize],
+        timestamp_period: f32,
+        timestamp_valid_bits: u32,
+        pipeline_statistic_flags: vk::QueryPipelineStatisticFlags,
+    ) -> Result<Self, RenderCtxError> {
         unsafe {
+            let uniform_buffer = Buffer::new_cpu_to_gpu(
+                allocator,
+                mem::size_of::<FrameUniform>() as vk::DeviceSize,
+                vk::BufferUsageFlags::UNIFORM_BUFFER,
+            )
+            .map_err(RenderCtxError::other("create a uniform buffer"))?;
+
+            let profiler = GpuProfiler::new(device.clone(), timestamp_period, timestamp_valid_bits)
+                .map_err(RenderCtxError::vulkan("create a timestamp query pool"))?;
+            let pipeline_statistics_query = if pipeline_statistic_flags.is_empty() {
+                None
+            } else {
+                Some(
+                    PipelineStatisticsQuery::new(device.clone(), pipeline_statistic_flags)
+                        .map_err(RenderCtxError::vulkan(
+                            "create a pipeline statistics query pool",
+                        ))?,
+                )
+            };
+
+            //the handles are filled in one by one, dropping a partially created frame destroys only those that exist
+            let mut frame = Self {
+                command_pool: vk::CommandPool::null(),
+                command_buffer: vk::CommandBuffer::null(),
+
+                present_semaphore: vk::Semaphore::null(),
+                render_semaphore: vk::Semaphore::null(),
+
+                fence: vk::Fence::null(),
+                uniform_buffer,
+
+                descriptor_pool: vk::DescriptorPool::null(),
+                descriptor_set: vk::DescriptorSet::null(),
+
+                profiler,
+                pipeline_statistics_query,
+
+                device: device.clone(),
+            };
+
             let command_pool_create_info = vk::CommandPoolCreateInfo::default()
                 .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
-                .queue_family_index(0);
+                .queue_family_index(queue_family_index);
 
-            let command_pool = device
+            frame.command_pool = device
                 .create_command_pool(&command_pool_create_info, None)
-                .unwrap();
+                .map_err(RenderCtxError::vulkan("create a command pool"))?;
 
             let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::default()
-                .command_pool(command_pool)
+                .command_pool(frame.command_pool)
                 .level(vk::CommandBufferLevel::PRIMARY)
                 .command_buffer_count(1);
 
-            let command_buffer = device
+            frame.command_buffer = device
                 .allocate_command_buffers(&command_buffer_allocate_info)
-                .unwrap()[0];
+                .map_err(RenderCtxError::vulkan("allocate a command buffer"))?[0];
 
             let semaphore_create_info = vk::SemaphoreCreateInfo::default();
 
-            let present_semaphore = device
+            frame.present_semaphore = device
                 .create_semaphore(&semaphore_create_info, None)
-                .unwrap();
-            let render_semaphore = device
+                .map_err(RenderCtxError::vulkan("create a semaphore"))?;
+            frame.render_semaphore = device
                 .create_semaphore(&semaphore_create_info, None)
-                .unwrap();
+                .map_err(RenderCtxError::vulkan("create a semaphore"))?;
 
             let fence_create_info =
                 vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED);
 
-            let fence = device.create_fence(&fence_create_info, None).unwrap();
-
-            let uniform_buffer = Buffer::new_cpu_to_gpu(
-                allocator.clone(),
-                mem::size_of::<Mat4>() as vk::DeviceSize,
-                vk::BufferUsageFlags::UNIFORM_BUFFER,
-            )
-            .unwrap();
-
-            let pool_size = DescriptorPoolSize::default()
-                .descriptor_count(1)
-                .ty(vk::DescriptorType::UNIFORM_BUFFER);
+            frame.fence = device
+                .create_fence(&fence_create_info, None)
+                .map_err(RenderCtxError::vulkan("create a fence"))?;
 
             let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo::default()
                 .max_sets(1)
-                .pool_sizes(slice::from_ref(&pool_size));
+                .pool_sizes(descriptor_pool_sizes);
 
-            let descriptor_pool = device
+            frame.descriptor_pool = device
                 .create_descriptor_pool(&descriptor_pool_create_info, None)
-                .unwrap();
+                .map_err(RenderCtxError::vulkan("create a descriptor pool"))?;
 
             let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo::default()
-                .descriptor_pool(descriptor_pool)
+                .descriptor_pool(frame.descriptor_pool)
                 .set_layouts(slice::from_ref(&descriptor_set_layout));
 
-            let descriptor_set = device
+            frame.descriptor_set = device
                 .allocate_descriptor_sets(&descriptor_set_allocate_info)
-                .unwrap()[0];
+                .map_err(RenderCtxError::vulkan("allocate a descriptor set"))?[0];
 
             let descriptor_buffer_info = DescriptorBufferInfo::default()
-                .buffer(uniform_buffer.buffer)
-                .range(uniform_buffer.size);
+                .buffer(frame.uniform_buffer.buffer)
+                .range(frame.uniform_buffer.size);
 
             let write_descriptor_set = vk::WriteDescriptorSet::default()
-                .dst_set(descriptor_set)
+                .dst_set(frame.descriptor_set)
                 .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
                 .buffer_info(slice::from_ref(&descriptor_buffer_info));
 
             device.update_descriptor_sets(slice::from_ref(&write_descriptor_set), &[]);
 
-            Self {
-                command_pool,
-                command_buffer,
-
-                present_semaphore,
-                render_semaphore,
-
-                fence,
-                uniform_buffer,
-                descriptor_pool,
-                descriptor_set,
-
-                device,
-            }
+            debug_names.set_object_name(
+                frame.command_buffer,
+                &format!("frame {} command buffer", index),
+            );
+            debug_names.set_object_name(
+                frame.uniform_buffer.buffer,
+                &format!("frame {} uniform buffer", index),
+            );
+            debug_names.set_object_name(
+                frame.descriptor_set,
+                &format!("frame {} descriptor set", index),
+            );
+
+            Ok(frame)
         }
     }
+
+    //must not be called while the frame is in flight, buffers the shaders don't use are skipped
+    pub unsafe fn write_geometry_descriptors(
+        &self,
+        geometry: &Geometry,
+        pipeline_reflection: &PipelineReflection,
+    ) {
+        let bindings: Vec<_> = geometry
+            .bindings()
+            .into_iter()
+            .filter(|(binding, _)| pipeline_reflection.binding(0, *binding).is_some())
+            .collect();
+
+        let descriptor_buffer_infos: Vec<_> = bindings
+            .iter()
+            .map(|(_, buffer)| {
+                DescriptorBufferInfo::default()
+                    .buffer(buffer.buffer)
+                    .range(vk::WHOLE_SIZE)
+            })
+            .collect();
+
+        let write_descriptor_sets: Vec<_> = bindings
+            .iter()
+            .zip(descriptor_buffer_infos.iter())
+            .map(|((binding, _), descriptor_buffer_info)| {
+                vk::WriteDescriptorSet::default()
+                    .dst_set(self.descriptor_set)
+                    .dst_binding(*binding)
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                    .buffer_info(slice::from_ref(descriptor_buffer_info))
+            })
+            .collect();
+
+        self.device
+            .update_descriptor_sets(&write_descriptor_sets, &[]);
+    }
 }
 
 impl Drop for Frame {
@@ -129,8 +221,10 @@ impl Drop for Frame {
             self.device.destroy_semaphore(self.render_semaphore, None);
             self.device.destroy_semaphore(self.present_semaphore, None);
 
-            self.device
-                .free_command_buffers(self.command_pool, slice::from_ref(&self.command_buffer));
+            if self.command_buffer != vk::CommandBuffer::null() {
+                self.device
+                    .free_command_buffers(self.command_pool, slice::from_ref(&self.command_buffer));
+            }
             self.device.destroy_command_pool(self.command_pool, None);
         }
     }
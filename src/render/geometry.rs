@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use anyhow::{ensure, Result};
+use ash::{vk, Device};
+use glam::{Mat4, Vec4};
+use vk_mem::Allocator;
+
+use crate::{
+    asset::{scene::Scene, Vertex},
+    render::{
+        meshlet::{self, MeshletMesh},
+        util, Buffer,
+    },
+};
+
+//bindings of the geometry storage buffers in descriptor set 0, binding 0 is the camera uniform
+pub const VERTEX_BINDING: u32 = 1;
+pub const MESHLET_BINDING: u32 = 2;
+pub const VERTEX_INDEX_BINDING: u32 = 3;
+pub const PRIMITIVE_INDEX_BINDING: u32 = 4;
+pub const MESHLET_BOUNDS_BINDING: u32 = 5;
+
+//meshlets tested by a single task shader workgroup, must match local_size_x in the task shaders
+pub const TASK_WORKGROUP_SIZE: u32 = 32;
+
+//laid out like the push constant block in the task, mesh and vertex shaders
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct DrawPushConstants {
+    pub model_matrix: Mat4,
+    pub base_color_factor: Vec4,
+    pub meshlet_offset: u32,
+    pub meshlet_count: u32,
+}
+
+//the block in the shaders ends with meshlet_count, the rust struct is padded to the alignment of Mat4
+pub const DRAW_PUSH_CONSTANTS_SIZE: u32 = 88;
+
+//where a primitive of the scene is in the shared buffers
+#[derive(Clone, Copy, Debug)]
+pub struct PrimitiveRange {
+    pub meshlet_offset: u32,
+    pub meshlet_count: u32,
+    pub first_index: u32,
+    pub index_count: u32,
+    pub vertex_offset: i32,
+}
+
+impl PrimitiveRange {
+    pub fn task_count(&self) -> u32 {
+        (self.meshlet_count + TASK_WORKGROUP_SIZE - 1) / TASK_WORKGROUP_SIZE
+    }
+}
+
+//a primitive drawn by a node, with the world transform of the node and the material factors of the primitive
+#[derive(Clone, Copy, Debug)]
+pub struct Instance {
+    //index into Geometry::primitives
+    pub primitive: usize,
+    pub transform: Mat4,
+    pub base_color_factor: Vec4,
+}
+
+impl Instance {
+    pub fn push_constants(&self, range: &PrimitiveRange) -> DrawPushConstants {
+        DrawPushConstants {
+            model_matrix: self.transform,
+            base_color_factor: self.base_color_factor,
+            meshlet_offset: range.meshlet_offset,
+            meshlet_count: range.meshlet_count,
+        }
+    }
+}
+
+//every primitive of a scene in one set of buffers, meshlet and vertex indices are relative to the whole buffers
+pub struct Geometry {
+    pub vertex_buffer: Buffer,
+    pub meshlet_buffer: Buffer,
+    pub vertex_index_buffer: Buffer,
+    pub primitive_index_buffer: Buffer,
+    pub meshlet_bounds_buffer: Buffer,
+
+    //only used by the vertex shader fallback
+    pub index_buffer: Buffer,
+
+    pub primitives: Vec<PrimitiveRange>,
+    //in the order of Scene::draws
+    pub instances: Vec<Instance>,
+}
+
+impl Geometry {
+    pub unsafe fn new(
+        device: &Device,
+        allocator: &Arc<Allocator>,
+        queue_family_index: u32,
+        queue: vk::Queue,
+        scene: &Scene,
+    ) -> Result<Self> {
+        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut indices = Vec::new();
+        let mut meshlet_mesh = MeshletMesh::default();
+        let mut primitives = Vec::new();
+
+        //primitives are uploaded once per mesh, however many nodes draw them
+        let mut first_primitives = Vec::with_capacity(scene.meshes.len());
+        for scene_mesh in &scene.meshes {
+            first_primitives.push(primitives.len());
+
+            for primitive in &scene_mesh.primitives {
+                let mesh = &primitive.mesh;
+                let primitive_meshlets = meshlet::build_meshlets(
+                    &mesh.positions(),
+                    &mesh.indices,
+                    meshlet::MAX_VERTICES,
+                    meshlet::MAX_PRIMITIVES,
+                )?;
+
+                primitives.push(PrimitiveRange {
+                    meshlet_offset: meshlet_mesh.meshlets.len() as u32,
+                    meshlet_count: primitive_meshlets.meshlets.len() as u32,
+                    first_index: indices.len() as u32,
+                    index_count: mesh.indices.len() as u32,
+                    vertex_offset: vertices.len() as i32,
+                });
+
+                meshlet_mesh.append(&primitive_meshlets, vertices.len() as u32);
+                vertices.extend_from_slice(&mesh.vertices);
+                indices.extend_from_slice(&mesh.indices);
+            }
+        }
+
+        let instances: Vec<_> = scene
+            .draws()
+            .map(|draw| Instance {
+                primitive: first_primitives[draw.mesh_index] + draw.primitive_index,
+                transform: draw.transform,
+                base_color_factor: draw.material.base_color_factor,
+            })
+            .collect();
+
+        //empty buffers can not be created
+        ensure!(
+            !meshlet_mesh.meshlets.is_empty() && !instances.is_empty(),
+            "The scene contains no triangles to draw"
+        );
+
+        //the mesh shader reads primitive indices as packed 32 bit words
+        let mut primitive_indices = meshlet_mesh.primitive_indices.clone();
+        primitive_indices.resize((primitive_indices.len() + 3) & !3, 0);
+
+        let usage = vk::BufferUsageFlags::STORAGE_BUFFER;
+
+        Ok(Self {
+            vertex_buffer: util::create_gpu_only_buffer_with_data(
+                device,
+                allocator,
+                queue_family_index,
+                queue,
+                util::as_bytes(&vertices),
+                usage | vk::BufferUsageFlags::VERTEX_BUFFER,
+            )?,
+            meshlet_buffer: util::create_gpu_only_buffer_with_data(
+                device,
+                allocator,
+                queue_family_index,
+                queue,
+                util::as_bytes(&meshlet_mesh.meshlets),
+                usage,
+            )?,
+            vertex_index_buffer: util::create_gpu_only_buffer_with_data(
+                device,
+                allocator,
+                queue_family_index,
+                queue,
+                util::as_bytes(&meshlet_mesh.vertex_indices),
+                usage,
+            )?,
+            primitive_index_buffer: util::create_gpu_only_buffer_with_data(
+                device,
+                allocator,
+                queue_family_index,
+                queue,
+                &primitive_indices,
+                usage,
+            )?,
+            meshlet_bounds_buffer: util::create_gpu_only_buffer_with_data(
+                device,
+                allocator,
+                queue_family_index,
+                queue,
+                util::as_bytes(&meshlet_mesh.bounds),
+                usage,
+            )?,
+
+            index_buffer: util::create_gpu_only_buffer_with_data(
+                device,
+                allocator,
+                queue_family_index,
+                queue,
+                util::as_bytes(&indices),
+                vk::BufferUsageFlags::INDEX_BUFFER,
+            )?,
+
+            primitives,
+            instances,
+        })
+    }
+
+    pub fn bindings(&self) -> [(u32, &Buffer); 5] {
+        [
+            (VERTEX_BINDING, &self.vertex_buffer),
+            (MESHLET_BINDING, &self.meshlet_buffer),
+            (VERTEX_INDEX_BINDING, &self.vertex_index_buffer),
+            (PRIMITIVE_INDEX_BINDING, &self.primitive_index_buffer),
+            (MESHLET_BOUNDS_BINDING, &self.meshlet_bounds_buffer),
+        ]
+    }
+}
@@ -0,0 +1,152 @@
+use std::ffi::CStr;
+
+use ash::{extensions::ext, extensions::nv, vk, Device, Instance};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshShaderVariant {
+    Ext,
+    Nv,
+}
+
+impl MeshShaderVariant {
+    //in order of preference
+    pub const ALL: [Self; 2] = [Self::Ext, Self::Nv];
+
+    pub fn extension_name(self) -> &'static CStr {
+        match self {
+            Self::Ext => ext::MeshShader::name(),
+            Self::Nv => nv::MeshShader::name(),
+        }
+    }
+
+    pub fn task_stage(self) -> vk::ShaderStageFlags {
+        match self {
+            Self::Ext => vk::ShaderStageFlags::TASK_EXT,
+            Self::Nv => vk::ShaderStageFlags::TASK_NV,
+        }
+    }
+
+    pub fn mesh_stage(self) -> vk::ShaderStageFlags {
+        match self {
+            Self::Ext => vk::ShaderStageFlags::MESH_EXT,
+            Self::Nv => vk::ShaderStageFlags::MESH_NV,
+        }
+    }
+
+    pub fn task_shader_path(self) -> &'static str {
+        match self {
+            Self::Ext => "example.ext.task.spv",
+            Self::Nv => "example.task.spv",
+        }
+    }
+
+    pub fn mesh_shader_path(self) -> &'static str {
+        match self {
+            Self::Ext => "example.ext.mesh.spv",
+            Self::Nv => "example.mesh.spv",
+        }
+    }
+}
+
+pub enum MeshShaderLoader {
+    Ext(ext::MeshShader),
+    Nv(nv::MeshShader),
+}
+
+impl MeshShaderLoader {
+    pub fn new(variant: MeshShaderVariant, instance: &Instance, device: &Device) -> Self {
+        match variant {
+            MeshShaderVariant::Ext => Self::Ext(ext::MeshShader::new(instance, device)),
+            MeshShaderVariant::Nv => Self::Nv(nv::MeshShader::new(instance, device)),
+        }
+    }
+
+    pub fn variant(&self) -> MeshShaderVariant {
+        match self {
+            Self::Ext(_) => MeshShaderVariant::Ext,
+            Self::Nv(_) => MeshShaderVariant::Nv,
+        }
+    }
+
+    //NV only supports one dimensional dispatches, group_count_y and group_count_z must be 1 there
+    pub unsafe fn cmd_draw_mesh_tasks(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        group_count_x: u32,
+        group_count_y: u32,
+        group_count_z: u32,
+    ) {
+        match self {
+            Self::Ext(loader) => loader.cmd_draw_mesh_tasks(
+                command_buffer,
+                group_count_x,
+                group_count_y,
+                group_count_z,
+            ),
+            Self::Nv(loader) => {
+                debug_assert!(group_count_y == 1 && group_count_z == 1);
+                loader.cmd_draw_mesh_tasks(command_buffer, group_count_x, 0)
+            }
+        }
+    }
+
+    //the buffer holds vk::DrawMeshTasksIndirectCommandEXT or vk::DrawMeshTasksIndirectCommandNV depending on the variant
+    pub unsafe fn cmd_draw_mesh_tasks_indirect(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        match self {
+            Self::Ext(loader) => loader.cmd_draw_mesh_tasks_indirect(
+                command_buffer,
+                buffer,
+                offset,
+                draw_count,
+                stride,
+            ),
+            Self::Nv(loader) => loader.cmd_draw_mesh_tasks_indirect(
+                command_buffer,
+                buffer,
+                offset,
+                draw_count,
+                stride,
+            ),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn cmd_draw_mesh_tasks_indirect_count(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        count_buffer: vk::Buffer,
+        count_buffer_offset: vk::DeviceSize,
+        max_draw_count: u32,
+        stride: u32,
+    ) {
+        match self {
+            Self::Ext(loader) => loader.cmd_draw_mesh_tasks_indirect_count(
+                command_buffer,
+                buffer,
+                offset,
+                count_buffer,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            ),
+            Self::Nv(loader) => loader.cmd_draw_mesh_tasks_indirect_count(
+                command_buffer,
+                buffer,
+                offset,
+                count_buffer,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            ),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::{bail, Result};
+use glam::Vec3;
+
+pub const MAX_VERTICES: usize = 64;
+pub const MAX_PRIMITIVES: usize = 126;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct Meshlet {
+    pub vertex_offset: u32,
+    pub primitive_offset: u32,
+    pub vertex_count: u32,
+    pub primitive_count: u32,
+}
+
+//laid out to match the std430 struct read by the task shader
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct MeshletBounds {
+    pub center: Vec3,
+    pub radius: f32,
+
+    //a meshlet is back facing if dot(center - eye, cone_axis) >= cone_cutoff * |center - eye| + radius,
+    //a cutoff of 1 disables cone culling
+    pub cone_axis: Vec3,
+    pub cone_cutoff: f32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MeshletMesh {
+    pub meshlets: Vec<Meshlet>,
+    pub bounds: Vec<MeshletBounds>,
+
+    //indices into the source vertex buffer, referenced by Meshlet::vertex_offset
+    pub vertex_indices: Vec<u32>,
+    //three local indices per primitive, referenced by Meshlet::primitive_offset
+    pub primitive_indices: Vec<u8>,
+}
+
+impl MeshletMesh {
+    pub fn meshlet_vertex_indices(&self, meshlet: &Meshlet) -> &[u32] {
+        let start = meshlet.vertex_offset as usize;
+        &self.vertex_indices[start..start + meshlet.vertex_count as usize]
+    }
+
+    pub fn meshlet_primitive_indices(&self, meshlet: &Meshlet) -> &[u8] {
+        let start = meshlet.primitive_offset as usize;
+        &self.primitive_indices[start..start + 3 * meshlet.primitive_count as usize]
+    }
+
+    //for meshlets of several meshes sharing one vertex buffer, base_vertex is where the vertices of other start
+    pub fn append(&mut self, other: &MeshletMesh, base_vertex: u32) {
+        let vertex_offset = self.vertex_indices.len() as u32;
+        let primitive_offset = self.primitive_indices.len() as u32;
+
+        self.meshlets
+            .extend(other.meshlets.iter().map(|meshlet| Meshlet {
+                vertex_offset: meshlet.vertex_offset + vertex_offset,
+                primitive_offset: meshlet.primitive_offset + primitive_offset,
+                ..*meshlet
+            }));
+        self.bounds.extend_from_slice(&other.bounds);
+        self.vertex_indices
+            .extend(other.vertex_indices.iter().map(|index| index + base_vertex));
+        self.primitive_indices
+            .extend_from_slice(&other.primitive_indices);
+    }
+}
+
+pub fn build_meshlets(
+    positions: &[Vec3],
+    indices: &[u32],
+    max_vertices: usize,
+    max_primitives: usize,
+) -> Result<MeshletMesh> {
+    if indices.len() % 3 != 0 {
+        bail!("Index count {} is not a multiple of three", indices.len());
+    }
+    if !(3..=256).contains(&max_vertices) {
+        bail!("max_vertices must be in 3..=256, got {}", max_vertices);
+    }
+    if max_primitives == 0 {
+        bail!("max_primitives must be at least one");
+    }
+    if let Some(index) = indices.iter().find(|i| **i as usize >= positions.len()) {
+        bail!(
+            "Index {} is out of range for {} vertices",
+            index,
+            positions.len()
+        );
+    }
+
+    let mut mesh = MeshletMesh::default();
+
+    //maps a source vertex to its local index in the meshlet that is currently being built
+    let mut local_indices = vec![u8::MAX; positions.len()];
+    let mut current = Meshlet::default();
+
+    for triangle in indices.chunks_exact(3) {
+        let new_vertices = triangle
+            .iter()
+            .enumerate()
+            .filter(|(i, index)| {
+                !is_local(&mesh, &current, &local_indices, **index)
+                    && !triangle[..*i].contains(index)
+            })
+            .count();
+
+        if current.vertex_count as usize + new_vertices > max_vertices
+            || current.primitive_count as usize + 1 > max_primitives
+        {
+            finish_meshlet(&mut mesh, &mut current, &mut local_indices, positions);
+        }
+
+        for index in triangle {
+            if !is_local(&mesh, &current, &local_indices, *index) {
+                local_indices[*index as usize] = current.vertex_count as u8;
+                mesh.vertex_indices.push(*index);
+                current.vertex_count += 1;
+            }
+            mesh.primitive_indices.push(local_indices[*index as usize]);
+        }
+        current.primitive_count += 1;
+    }
+
+    finish_meshlet(&mut mesh, &mut current, &mut local_indices, positions);
+
+    Ok(mesh)
+}
+
+fn is_local(mesh: &MeshletMesh, current: &Meshlet, local_indices: &[u8], index: u32) -> bool {
+    let local_index = local_indices[index as usize] as u32;
+    //max_vertices may be 256, so u8::MAX alone is not a reliable sentinel
+    local_index < current.vertex_count
+        && mesh.vertex_indices[(current.vertex_offset + local_index) as usize] == index
+}
+
+fn finish_meshlet(
+    mesh: &mut MeshletMesh,
+    current: &mut Meshlet,
+    local_indices: &mut [u8],
+    positions: &[Vec3],
+) {
+    if current.primitive_count == 0 {
+        return;
+    }
+
+    let vertex_indices = mesh.meshlet_vertex_indices(current);
+    vertex_indices
+        .iter()
+        .for_each(|index| local_indices[*index as usize] = u8::MAX);
+
+    let positions: Vec<_> = vertex_indices
+        .iter()
+        .map(|index| positions[*index as usize])
+        .collect();
+    let bounds = compute_bounds(&positions, mesh.meshlet_primitive_indices(current));
+    mesh.bounds.push(bounds);
+    mesh.meshlets.push(*current);
+
+    *current = Meshlet {
+        vertex_offset: mesh.vertex_indices.len() as u32,
+        primitive_offset: mesh.primitive_indices.len() as u32,
+        vertex_count: 0,
+        primitive_count: 0,
+    };
+}
+
+fn compute_bounds(positions: &[Vec3], primitive_indices: &[u8]) -> MeshletBounds {
+    let (min, max) = positions.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), p| (min.min(*p), max.max(*p)),
+    );
+
+    let center = (min + max) * 0.5;
+    let radius = positions
+        .iter()
+        .fold(0f32, |radius, p| radius.max(p.distance(center)));
+
+    let normals: Vec<_> = primitive_indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| positions[i as usize]);
+            (b - a).cross(c - a).normalize_or_zero()
+        })
+        //degenerate triangles can face any direction
+        .filter(|normal| *normal != Vec3::ZERO)
+        .collect();
+
+    let cone_axis = normals.iter().sum::<Vec3>().normalize_or_zero();
+    let min_dot = normals
+        .iter()
+        .fold(1f32, |min_dot, normal| min_dot.min(normal.dot(cone_axis)));
+
+    //the normals span a half space or more, so some triangle is front facing from every direction
+    if normals.is_empty() || cone_axis == Vec3::ZERO || min_dot <= 0.0 {
+        return MeshletBounds {
+            center,
+            radius,
+            cone_axis: Vec3::ZERO,
+            cone_cutoff: 1.0,
+        };
+    }
+
+    MeshletBounds {
+        center,
+        radius,
+        cone_axis,
+        //sine of the cone half angle, i.e. cosine of the angle between the axis and the cone surface + 90 degrees
+        cone_cutoff: (1.0 - min_dot * min_dot).sqrt(),
+    }
+}
@@ -1,9 +1,30 @@
 pub mod buffer;
 pub mod camera;
+pub mod cleanup;
+pub mod config;
+pub mod culling;
+pub mod debug_utils;
+pub mod device_selection;
+pub mod error;
 pub mod frame;
+pub mod geometry;
 pub mod math_util;
+pub mod mesh_shader;
+pub mod meshlet;
+pub mod offscreen;
+pub mod output;
+pub mod pipeline_cache;
+pub mod pipeline_statistics;
+pub mod profiler;
+pub mod reflection;
 pub mod render_ctx;
 pub mod renderer;
+#[cfg(feature = "hot-reload")]
+pub mod shader_compiler;
+#[cfg(feature = "hot-reload")]
+pub mod shader_reload;
+pub mod shaders;
+pub mod spirv;
 pub mod util;
 
 pub use buffer::*;
@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use ash::{vk, Device};
+use vk_mem::{Allocation, AllocationCreateInfo, Allocator, MemoryUsage};
+
+use crate::render::{render_ctx::OFFSCREEN_FORMAT, Buffer};
+
+//color target of a headless RenderCtx, read back into a host visible buffer after each frame
+pub struct OffscreenTarget {
+    pub image: vk::Image,
+    pub allocation: Allocation,
+    pub image_view: vk::ImageView,
+    pub extent: vk::Extent2D,
+
+    pub readback_buffer: Buffer,
+
+    device: Arc<Device>,
+    allocator: Arc<Allocator>,
+}
+
+impl OffscreenTarget {
+    pub fn new(
+        device: Arc<Device>,
+        allocator: Arc<Allocator>,
+        extent: vk::Extent2D,
+    ) -> Result<Self> {
+        let image_create_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(OFFSCREEN_FORMAT)
+            .extent(
+                vk::Extent3D::default()
+                    .width(extent.width)
+                    .height(extent.height)
+                    .depth(1),
+            )
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+
+        let allocation_create_info = AllocationCreateInfo::new().usage(MemoryUsage::GpuOnly);
+        let (image, allocation) =
+            unsafe { allocator.create_image(&image_create_info, &allocation_create_info)? };
+
+        let image_view_create_info = vk::ImageViewCreateInfo::default()
+            .image(image)
+            .view_type(vk::ImageViewType::TYPE_2D)
+            .format(OFFSCREEN_FORMAT)
+            .components(Default::default())
+            .subresource_range(
+                vk::ImageSubresourceRange::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .level_count(1)
+                    .layer_count(1),
+            );
+
+        let image_view = match unsafe { device.create_image_view(&image_view_create_info, None) } {
+            Ok(image_view) => image_view,
+            Err(result) => {
+                unsafe { allocator.destroy_image(image, allocation) };
+                return Err(result.into());
+            }
+        };
+
+        let readback_buffer = Buffer::new_gpu_to_cpu(
+            allocator.clone(),
+            4 * extent.width as vk::DeviceSize * extent.height as vk::DeviceSize,
+            vk::BufferUsageFlags::TRANSFER_DST,
+        );
+        let readback_buffer = match readback_buffer {
+            Ok(readback_buffer) => readback_buffer,
+            Err(e) => unsafe {
+                device.destroy_image_view(image_view, None);
+                allocator.destroy_image(image, allocation);
+                return Err(e);
+            },
+        };
+
+        Ok(Self {
+            image,
+            allocation,
+            image_view,
+            extent,
+
+            readback_buffer,
+
+            device,
+            allocator,
+        })
+    }
+
+    //converts the mapped readback buffer from the bgra offscreen format to tightly packed rgba8, still srgb encoded
+    pub fn read_rgba8(&self) -> Vec<u8> {
+        let size = self.readback_buffer.size as usize;
+        let bgra =
+            unsafe { std::slice::from_raw_parts(self.readback_buffer.memory.unwrap(), size) };
+
+        bgra.chunks_exact(4)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect()
+    }
+}
+
+impl Drop for OffscreenTarget {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_image_view(self.image_view, None);
+            self.allocator.destroy_image(self.image, self.allocation);
+        }
+    }
+}
@@ -0,0 +1,446 @@
+use std::{borrow::Cow, mem, slice, sync::Arc};
+
+use anyhow::{bail, ensure, Context, Result};
+use ash::{vk, Device};
+use vk_mem::{Allocation, Allocator};
+
+use crate::render::{
+    config::HdrOutput,
+    debug_utils::DebugNames,
+    reflection::{self, PipelineReflection, ShaderReflection},
+    spirv::{self, SpirvVersion},
+    util,
+};
+
+//linear and unbounded, the mesh pass renders into it and the output pass reads it
+pub const SCENE_COLOR_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+
+pub const VERTEX_SHADER_PATH: &str = "output.vert.spv";
+pub const FRAGMENT_SHADER_PATH: &str = "output.frag.spv";
+
+//in order of preference, the less common ones are only used if a surface supports nothing else
+const SRGB_FORMATS: [vk::Format; 3] = [
+    vk::Format::B8G8R8A8_SRGB,
+    vk::Format::R8G8B8A8_SRGB,
+    vk::Format::A8B8G8R8_SRGB_PACK32,
+];
+const UNORM_FORMATS: [vk::Format; 7] = [
+    vk::Format::B8G8R8A8_UNORM,
+    vk::Format::R8G8B8A8_UNORM,
+    vk::Format::A8B8G8R8_UNORM_PACK32,
+    vk::Format::A2B10G10R10_UNORM_PACK32,
+    vk::Format::A2R10G10B10_UNORM_PACK32,
+    vk::Format::R5G6B5_UNORM_PACK16,
+    vk::Format::B5G6R5_UNORM_PACK16,
+];
+const HDR10_FORMATS: [vk::Format; 2] = [
+    vk::Format::A2B10G10R10_UNORM_PACK32,
+    vk::Format::A2R10G10B10_UNORM_PACK32,
+];
+const SCRGB_FORMATS: [vk::Format; 1] = [vk::Format::R16G16B16A16_SFLOAT];
+
+//how the output pass turns linear scene color into what the target format and color space expect
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputTransform {
+    //tonemapped, the _SRGB format applies the transfer function on store
+    Srgb,
+    //tonemapped and encoded in the shader, for UNORM formats in the srgb color space
+    SrgbEncode,
+    //converted to rec. 2020 primaries and pq encoded, paper white is scaled to the configured brightness
+    Hdr10,
+    //linear, paper white is scaled to the configured brightness
+    ScRgb,
+}
+
+impl OutputTransform {
+    //matches the TRANSFORM_ constants in shaders/output.frag.glsl
+    pub fn shader_value(self) -> u32 {
+        match self {
+            Self::Srgb => 0,
+            Self::SrgbEncode => 1,
+            Self::Hdr10 => 2,
+            Self::ScRgb => 3,
+        }
+    }
+
+    pub fn is_hdr(self) -> bool {
+        matches!(self, Self::Hdr10 | Self::ScRgb)
+    }
+}
+
+//laid out like the push constant block in shaders/output.frag.glsl
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+struct OutputPushConstants {
+    transform: u32,
+    paper_white_nits: f32,
+}
+
+//hdr is only used if requested and supported, sdr prefers formats that apply the srgb transfer function in hardware
+//fails if the surface only supports formats the output pass has no transform for
+pub fn choose_surface_format(
+    supported: &[vk::SurfaceFormatKHR],
+    hdr_output: Option<HdrOutput>,
+) -> Result<(vk::SurfaceFormatKHR, OutputTransform)> {
+    let find = |color_space: vk::ColorSpaceKHR, formats: &[vk::Format]| {
+        formats.iter().find_map(|&format| {
+            supported
+                .iter()
+                .find(|surface_format| {
+                    surface_format.format == format && surface_format.color_space == color_space
+                })
+                .copied()
+        })
+    };
+
+    let hdr = match hdr_output {
+        Some(HdrOutput::Hdr10) => find(vk::ColorSpaceKHR::HDR10_ST2084_EXT, &HDR10_FORMATS)
+            .map(|surface_format| (surface_format, OutputTransform::Hdr10)),
+        Some(HdrOutput::ScRgb) => find(vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT, &SCRGB_FORMATS)
+            .map(|surface_format| (surface_format, OutputTransform::ScRgb)),
+        None => None,
+    };
+
+    let chosen = hdr
+        .or_else(|| {
+            find(vk::ColorSpaceKHR::SRGB_NONLINEAR, &SRGB_FORMATS)
+                .map(|surface_format| (surface_format, OutputTransform::Srgb))
+        })
+        .or_else(|| {
+            find(vk::ColorSpaceKHR::SRGB_NONLINEAR, &UNORM_FORMATS)
+                .map(|surface_format| (surface_format, OutputTransform::SrgbEncode))
+        });
+    if let Some(chosen) = chosen {
+        return Ok(chosen);
+    }
+
+    match supported {
+        //a single UNDEFINED entry means that any format may be used
+        [surface_format] if surface_format.format == vk::Format::UNDEFINED => Ok((
+            vk::SurfaceFormatKHR::default()
+                .format(vk::Format::B8G8R8A8_SRGB)
+                .color_space(vk::ColorSpaceKHR::SRGB_NONLINEAR),
+            OutputTransform::Srgb,
+        )),
+        _ => bail!(
+            "None of the supported surface formats can be rendered to: {:?}",
+            supported
+                .iter()
+                .map(|surface_format| (surface_format.format, surface_format.color_space))
+                .collect::<Vec<_>>()
+        ),
+    }
+}
+
+//tonemaps the scene color into the swapchain image or offscreen target with a fullscreen triangle
+pub struct OutputPass {
+    pub transform: OutputTransform,
+    pub paper_white_nits: f32,
+
+    //same extent as the target, see resize
+    pub scene_color_image: vk::Image,
+    pub scene_color_allocation: Allocation,
+    pub scene_color_image_view: vk::ImageView,
+
+    pub sampler: vk::Sampler,
+    pub descriptor_set_layout: vk::DescriptorSetLayout,
+    pub pipeline_layout: vk::PipelineLayout,
+    pub pipeline: vk::Pipeline,
+
+    pub descriptor_pool: vk::DescriptorPool,
+    pub descriptor_set: vk::DescriptorSet,
+
+    //kept to check reloaded shaders against the layout
+    pipeline_reflection: PipelineReflection,
+    target_format: vk::Format,
+
+    debug_names: DebugNames,
+    device: Arc<Device>,
+    allocator: Arc<Allocator>,
+}
+
+impl OutputPass {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        device: Arc<Device>,
+        allocator: Arc<Allocator>,
+        debug_names: DebugNames,
+        extent: vk::Extent2D,
+        target_format: vk::Format,
+        transform: OutputTransform,
+        paper_white_nits: f32,
+        pipeline_cache: vk::PipelineCache,
+        max_spirv_version: SpirvVersion,
+        load_shader: impl Fn(&str) -> Result<Cow<'static, [u32]>>,
+    ) -> Result<Self> {
+        let (vertex_code, fragment_code, pipeline_reflection) =
+            load_shaders(&load_shader, max_spirv_version)?;
+        ensure!(
+            pipeline_reflection.sets.len() == 1 && pipeline_reflection.sets.contains_key(&0),
+            "The output shaders must use exactly descriptor set 0"
+        );
+
+        unsafe {
+            let (scene_color_image, scene_color_allocation, scene_color_image_view) =
+                util::create_color_image(
+                    &device,
+                    &allocator,
+                    extent,
+                    SCENE_COLOR_FORMAT,
+                    vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+                )?;
+
+            //the handles are filled in one by one, dropping a partially created pass destroys only those that exist
+            let mut output_pass = Self {
+                transform,
+                paper_white_nits,
+
+                scene_color_image,
+                scene_color_allocation,
+                scene_color_image_view,
+
+                sampler: vk::Sampler::null(),
+                descriptor_set_layout: vk::DescriptorSetLayout::null(),
+                pipeline_layout: vk::PipelineLayout::null(),
+                pipeline: vk::Pipeline::null(),
+
+                descriptor_pool: vk::DescriptorPool::null(),
+                descriptor_set: vk::DescriptorSet::null(),
+
+                pipeline_reflection,
+                target_format,
+
+                debug_names,
+                device: device.clone(),
+                allocator,
+            };
+
+            let sampler_create_info = vk::SamplerCreateInfo::default()
+                .mag_filter(vk::Filter::NEAREST)
+                .min_filter(vk::Filter::NEAREST)
+                .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE);
+            output_pass.sampler = device.create_sampler(&sampler_create_info, None)?;
+
+            output_pass.descriptor_set_layout = output_pass
+                .pipeline_reflection
+                .create_descriptor_set_layouts(&device)?[0];
+            output_pass.pipeline_layout = output_pass
+                .pipeline_reflection
+                .create_pipeline_layout(&device, &[output_pass.descriptor_set_layout])?;
+
+            let descriptor_pool_sizes = output_pass.pipeline_reflection.descriptor_pool_sizes(0);
+            let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo::default()
+                .max_sets(1)
+                .pool_sizes(&descriptor_pool_sizes);
+            output_pass.descriptor_pool =
+                device.create_descriptor_pool(&descriptor_pool_create_info, None)?;
+
+            let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo::default()
+                .descriptor_pool(output_pass.descriptor_pool)
+                .set_layouts(slice::from_ref(&output_pass.descriptor_set_layout));
+            output_pass.descriptor_set =
+                device.allocate_descriptor_sets(&descriptor_set_allocate_info)?[0];
+
+            output_pass.pipeline =
+                output_pass.create_pipeline(&vertex_code, &fragment_code, pipeline_cache)?;
+
+            output_pass.write_descriptor_set();
+            output_pass.set_debug_names();
+
+            Ok(output_pass)
+        }
+    }
+
+    //recreates the scene color for a new target extent, nothing may be in flight
+    pub unsafe fn resize(&mut self, extent: vk::Extent2D) -> Result<()> {
+        let (scene_color_image, scene_color_allocation, scene_color_image_view) =
+            util::create_color_image(
+                &self.device,
+                &self.allocator,
+                extent,
+                SCENE_COLOR_FORMAT,
+                vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+            )?;
+
+        self.device
+            .destroy_image_view(self.scene_color_image_view, None);
+        self.allocator
+            .destroy_image(self.scene_color_image, self.scene_color_allocation);
+
+        self.scene_color_image = scene_color_image;
+        self.scene_color_allocation = scene_color_allocation;
+        self.scene_color_image_view = scene_color_image_view;
+
+        self.write_descriptor_set();
+        self.set_debug_names();
+
+        Ok(())
+    }
+
+    //checks the reloaded shaders against the current layout, the result is passed to replace_pipeline
+    pub unsafe fn create_reloaded_pipeline(
+        &self,
+        pipeline_cache: vk::PipelineCache,
+        max_spirv_version: SpirvVersion,
+        load_shader: impl Fn(&str) -> Result<Cow<'static, [u32]>>,
+    ) -> Result<vk::Pipeline> {
+        let (vertex_code, fragment_code, pipeline_reflection) =
+            load_shaders(&load_shader, max_spirv_version)?;
+        self.pipeline_reflection
+            .ensure_layout_compatible(&pipeline_reflection)
+            .context(
+                "The reloaded output shaders do not fit the pipeline layout, restart to apply them",
+            )?;
+
+        self.create_pipeline(&vertex_code, &fragment_code, pipeline_cache)
+    }
+
+    //nothing may be in flight
+    pub unsafe fn replace_pipeline(&mut self, pipeline: vk::Pipeline) {
+        self.device.destroy_pipeline(self.pipeline, None);
+        self.pipeline = pipeline;
+        self.set_debug_names();
+    }
+
+    unsafe fn create_pipeline(
+        &self,
+        vertex_code: &[u32],
+        fragment_code: &[u32],
+        pipeline_cache: vk::PipelineCache,
+    ) -> Result<vk::Pipeline> {
+        let vertex_shader = util::create_shader_module_from_spirv(&self.device, vertex_code)?;
+        let pipeline = util::create_shader_module_from_spirv(&self.device, fragment_code).and_then(
+            |fragment_shader| {
+                let pipeline = util::create_fullscreen_pipeline(
+                    &self.device,
+                    pipeline_cache,
+                    vertex_shader,
+                    fragment_shader,
+                    self.pipeline_layout,
+                    self.target_format,
+                );
+                self.device.destroy_shader_module(fragment_shader, None);
+                pipeline
+            },
+        );
+        self.device.destroy_shader_module(vertex_shader, None);
+
+        pipeline
+    }
+
+    //the scene color must be in SHADER_READ_ONLY_OPTIMAL and rendering to the target must have begun
+    pub unsafe fn draw(&self, command_buffer: vk::CommandBuffer, extent: vk::Extent2D) {
+        self.device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline,
+        );
+        self.device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline_layout,
+            0,
+            slice::from_ref(&self.descriptor_set),
+            &[],
+        );
+
+        let push_constants = OutputPushConstants {
+            transform: self.transform.shader_value(),
+            paper_white_nits: self.paper_white_nits,
+        };
+        self.device.cmd_push_constants(
+            command_buffer,
+            self.pipeline_layout,
+            vk::ShaderStageFlags::FRAGMENT,
+            0,
+            slice::from_raw_parts(
+                (&push_constants as *const OutputPushConstants).cast(),
+                mem::size_of::<OutputPushConstants>(),
+            ),
+        );
+
+        let viewport = vk::Viewport::default()
+            .width(extent.width as _)
+            .height(extent.height as _)
+            .max_depth(1.0);
+        let scissor = vk::Rect2D::default().extent(extent);
+
+        self.device
+            .cmd_set_viewport(command_buffer, 0, slice::from_ref(&viewport));
+        self.device
+            .cmd_set_scissor(command_buffer, 0, slice::from_ref(&scissor));
+
+        self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+    }
+
+    unsafe fn write_descriptor_set(&self) {
+        let descriptor_image_info = vk::DescriptorImageInfo::default()
+            .sampler(self.sampler)
+            .image_view(self.scene_color_image_view)
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+
+        let write_descriptor_set = vk::WriteDescriptorSet::default()
+            .dst_set(self.descriptor_set)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(slice::from_ref(&descriptor_image_info));
+
+        self.device
+            .update_descriptor_sets(slice::from_ref(&write_descriptor_set), &[]);
+    }
+
+    unsafe fn set_debug_names(&self) {
+        self.debug_names
+            .set_object_name(self.scene_color_image, "scene color");
+        self.debug_names
+            .set_object_name(self.pipeline, "output pipeline");
+        self.debug_names
+            .set_object_name(self.descriptor_set, "output descriptor set");
+    }
+}
+
+impl Drop for OutputPass {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_pipeline(self.pipeline, None);
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+            self.device.destroy_sampler(self.sampler, None);
+
+            self.device
+                .destroy_image_view(self.scene_color_image_view, None);
+            self.allocator
+                .destroy_image(self.scene_color_image, self.scene_color_allocation);
+        }
+    }
+}
+
+//validated and reflected, the reflection is merged over both stages
+fn load_shaders(
+    load_shader: impl Fn(&str) -> Result<Cow<'static, [u32]>>,
+    max_spirv_version: SpirvVersion,
+) -> Result<(Cow<'static, [u32]>, Cow<'static, [u32]>, PipelineReflection)> {
+    let vertex_code = load_shader(VERTEX_SHADER_PATH)?;
+    let fragment_code = load_shader(FRAGMENT_SHADER_PATH)?;
+    for (name, code) in [
+        (VERTEX_SHADER_PATH, &vertex_code),
+        (FRAGMENT_SHADER_PATH, &fragment_code),
+    ] {
+        spirv::validate(code, max_spirv_version)
+            .with_context(|| format!("Failed to validate {}", name))?;
+    }
+
+    let pipeline_reflection = reflection::merge(&[
+        ShaderReflection::parse(&vertex_code)
+            .with_context(|| format!("Failed to reflect {}", VERTEX_SHADER_PATH))?,
+        ShaderReflection::parse(&fragment_code)
+            .with_context(|| format!("Failed to reflect {}", FRAGMENT_SHADER_PATH))?,
+    ])?;
+
+    Ok((vertex_code, fragment_code, pipeline_reflection))
+}
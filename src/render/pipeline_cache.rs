@@ -0,0 +1,153 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{ensure, Result};
+use ash::{vk, Device};
+
+//overrides default_directory
+pub const CACHE_DIRECTORY_VARIABLE: &str = "VULKAN_EXPERIMENTS_CACHE_DIR";
+
+//size of VkPipelineCacheHeaderVersionOne, which every driver puts in front of its cache data
+pub const HEADER_SIZE: usize = 32;
+const HEADER_VERSION_ONE: u32 = 1;
+
+//loaded from and saved to a file per device and driver, so switching either never hands the driver a foreign cache
+pub struct PipelineCache {
+    pub pipeline_cache: vk::PipelineCache,
+    path: PathBuf,
+
+    device: Arc<Device>,
+}
+
+impl PipelineCache {
+    //starts out empty if there is no usable cache for this device and driver
+    pub fn load(
+        device: Arc<Device>,
+        properties: &vk::PhysicalDeviceProperties,
+        directory: &Path,
+    ) -> Result<Self> {
+        let path = directory.join(file_name(properties));
+
+        let initial_data = match fs::read(&path) {
+            Ok(data) => match validate_header(&data, properties) {
+                Ok(()) => data,
+                Err(e) => {
+                    log::warn!("Discarding pipeline cache {}: {:#}", path.display(), e);
+                    Vec::new()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                log::warn!("Failed to read pipeline cache {}: {}", path.display(), e);
+                Vec::new()
+            }
+        };
+
+        let pipeline_cache_create_info =
+            vk::PipelineCacheCreateInfo::default().initial_data(&initial_data);
+        let pipeline_cache =
+            unsafe { device.create_pipeline_cache(&pipeline_cache_create_info, None)? };
+
+        Ok(Self {
+            pipeline_cache,
+            path,
+
+            device,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let data = unsafe { self.device.get_pipeline_cache_data(self.pipeline_cache)? };
+
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        //renamed into place, so an interrupted save never leaves a truncated cache behind
+        let temporary_path = self.path.with_extension("tmp");
+        fs::write(&temporary_path, &data)?;
+        fs::rename(&temporary_path, &self.path)?;
+
+        Ok(())
+    }
+}
+
+impl Drop for PipelineCache {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            log::warn!(
+                "Failed to save pipeline cache {}: {:#}",
+                self.path.display(),
+                e
+            );
+        }
+
+        let pipeline_cache = self.pipeline_cache;
+        unsafe {
+            self.device.destroy_pipeline_cache(pipeline_cache, None);
+        }
+    }
+}
+
+pub fn default_directory() -> PathBuf {
+    env::var_os(CACHE_DIRECTORY_VARIABLE)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| env::temp_dir().join("vulkan_experiments"))
+}
+
+//the driver version is not part of the vulkan header, so it is only checked through the file name
+pub fn file_name(properties: &vk::PhysicalDeviceProperties) -> String {
+    let uuid: String = properties
+        .pipeline_cache_uuid
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!(
+        "pipeline-cache-{:04x}-{:04x}-{:08x}-{}.bin",
+        properties.vendor_id, properties.device_id, properties.driver_version, uuid
+    )
+}
+
+//drivers are supposed to reject foreign caches themselves, but not all of them do so gracefully
+pub fn validate_header(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> Result<()> {
+    ensure!(
+        data.len() >= HEADER_SIZE,
+        "Only {} bytes, the header alone is {}",
+        data.len(),
+        HEADER_SIZE
+    );
+
+    //the header is little endian regardless of the host byte order
+    let word =
+        |index: usize| u32::from_le_bytes(data[index * 4..index * 4 + 4].try_into().unwrap());
+
+    let header_size = word(0) as usize;
+    ensure!(
+        (HEADER_SIZE..=data.len()).contains(&header_size),
+        "Invalid header size {}",
+        header_size
+    );
+    ensure!(
+        word(1) == HEADER_VERSION_ONE,
+        "Unknown header version {}",
+        word(1)
+    );
+    ensure!(
+        word(2) == properties.vendor_id && word(3) == properties.device_id,
+        "Created by device {:04x}:{:04x} instead of {:04x}:{:04x}",
+        word(2),
+        word(3),
+        properties.vendor_id,
+        properties.device_id
+    );
+    ensure!(
+        data[16..HEADER_SIZE] == properties.pipeline_cache_uuid,
+        "Created by a different driver"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::{cell::Cell, fmt, sync::Arc};
+
+use ash::{vk, Device};
+
+use crate::render::{device_selection::OptionalFeatures, mesh_shader::MeshShaderVariant};
+
+//upper bound for the number of counters a single query returns
+const MAX_COUNTERS: usize = 16;
+
+//None for counters that are not queried, either because the device lacks them or they do not apply to the pipeline
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PipelineStatistics {
+    pub input_assembly_vertices: Option<u64>,
+    pub input_assembly_primitives: Option<u64>,
+    pub vertex_shader_invocations: Option<u64>,
+    pub clipping_invocations: Option<u64>,
+    pub clipping_primitives: Option<u64>,
+    pub fragment_shader_invocations: Option<u64>,
+    pub task_shader_invocations: Option<u64>,
+    pub mesh_shader_invocations: Option<u64>,
+}
+
+//in the order the results are written, which is the order of the flag bits
+const COUNTERS: [(
+    vk::QueryPipelineStatisticFlags,
+    fn(&mut PipelineStatistics) -> &mut Option<u64>,
+); 8] = [
+    (
+        vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES,
+        |s| &mut s.input_assembly_vertices,
+    ),
+    (
+        vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES,
+        |s| &mut s.input_assembly_primitives,
+    ),
+    (
+        vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS,
+        |s| &mut s.vertex_shader_invocations,
+    ),
+    (vk::QueryPipelineStatisticFlags::CLIPPING_INVOCATIONS, |s| {
+        &mut s.clipping_invocations
+    }),
+    (vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES, |s| {
+        &mut s.clipping_primitives
+    }),
+    (
+        vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS,
+        |s| &mut s.fragment_shader_invocations,
+    ),
+    (
+        vk::QueryPipelineStatisticFlags::TASK_SHADER_INVOCATIONS_EXT,
+        |s| &mut s.task_shader_invocations,
+    ),
+    (
+        vk::QueryPipelineStatisticFlags::MESH_SHADER_INVOCATIONS_EXT,
+        |s| &mut s.mesh_shader_invocations,
+    ),
+];
+
+//empty if pipeline statistics are unsupported
+pub fn supported_flags(
+    mesh_shader_variant: Option<MeshShaderVariant>,
+    optional_features: &OptionalFeatures,
+) -> vk::QueryPipelineStatisticFlags {
+    if !optional_features.pipeline_statistics_query {
+        return vk::QueryPipelineStatisticFlags::empty();
+    }
+
+    let flags = vk::QueryPipelineStatisticFlags::CLIPPING_INVOCATIONS
+        | vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES
+        | vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS;
+
+    //input assembly and vertex shader counters must not be active while drawing with mesh shaders
+    match mesh_shader_variant {
+        None => {
+            flags
+                | vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES
+                | vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES
+                | vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS
+        }
+        Some(_) if optional_features.mesh_shader_queries => {
+            flags
+                | vk::QueryPipelineStatisticFlags::TASK_SHADER_INVOCATIONS_EXT
+                | vk::QueryPipelineStatisticFlags::MESH_SHADER_INVOCATIONS_EXT
+        }
+        Some(_) => flags,
+    }
+}
+
+//a single query around the draw of one frame
+pub struct PipelineStatisticsQuery {
+    query_pool: vk::QueryPool,
+    flags: vk::QueryPipelineStatisticFlags,
+    //whether the last recorded command buffer contains the query
+    is_recorded: Cell<bool>,
+
+    device: Arc<Device>,
+}
+
+impl PipelineStatisticsQuery {
+    pub fn new(
+        device: Arc<Device>,
+        flags: vk::QueryPipelineStatisticFlags,
+    ) -> Result<Self, vk::Result> {
+        let query_pool_create_info = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::PIPELINE_STATISTICS)
+            .query_count(1)
+            .pipeline_statistics(flags);
+
+        let query_pool = unsafe { device.create_query_pool(&query_pool_create_info, None)? };
+
+        Ok(Self {
+            query_pool,
+            flags,
+            is_recorded: Cell::new(false),
+
+            device,
+        })
+    }
+
+    //must be recorded outside of rendering, before begin
+    pub unsafe fn reset(&self, command_buffer: vk::CommandBuffer) {
+        self.is_recorded.set(false);
+        self.device
+            .cmd_reset_query_pool(command_buffer, self.query_pool, 0, 1);
+    }
+
+    pub unsafe fn begin(&self, command_buffer: vk::CommandBuffer) {
+        self.is_recorded.set(true);
+        self.device.cmd_begin_query(
+            command_buffer,
+            self.query_pool,
+            0,
+            vk::QueryControlFlags::empty(),
+        );
+    }
+
+    pub unsafe fn end(&self, command_buffer: vk::CommandBuffer) {
+        self.device
+            .cmd_end_query(command_buffer, self.query_pool, 0);
+    }
+
+    //call once the fence of the frame is signaled, the results of a submission are only returned once
+    pub unsafe fn take_results(&self) -> Option<PipelineStatistics> {
+        if !self.is_recorded.replace(false) {
+            return None;
+        }
+
+        let mut results = [[0u64; MAX_COUNTERS]];
+        self.device
+            .get_query_pool_results(
+                self.query_pool,
+                0,
+                &mut results,
+                vk::QueryResultFlags::TYPE_64,
+            )
+            .ok()?;
+
+        let mut statistics = PipelineStatistics::default();
+        let mut values = results[0].iter();
+        for (flag, counter) in COUNTERS {
+            if self.flags.contains(flag) {
+                *counter(&mut statistics) = values.next().copied();
+            }
+        }
+
+        Some(statistics)
+    }
+}
+
+impl Drop for PipelineStatisticsQuery {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_query_pool(self.query_pool, None);
+        }
+    }
+}
+
+//one counter per line, counters that were not queried are left out
+impl fmt::Display for PipelineStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counters = [
+            ("input assembly vertices", self.input_assembly_vertices),
+            ("input assembly primitives", self.input_assembly_primitives),
+            ("vertex shader invocations", self.vertex_shader_invocations),
+            ("task shader invocations", self.task_shader_invocations),
+            ("mesh shader invocations", self.mesh_shader_invocations),
+            ("clipping invocations", self.clipping_invocations),
+            ("clipping primitives", self.clipping_primitives),
+            (
+                "fragment shader invocations",
+                self.fragment_shader_invocations,
+            ),
+        ];
+
+        for (name, value) in counters {
+            if let Some(value) = value {
+                writeln!(f, "{:>28}: {}", name, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt,
+    io::{self, Write},
+    mem,
+    sync::Arc,
+};
+
+use ash::{vk, Device};
+
+//each scope needs two timestamps, scopes beyond this are not measured
+pub const MAX_SCOPES: u32 = 32;
+//number of frames kept for the chrome trace export
+pub const MAX_TRACE_FRAMES: usize = 600;
+
+#[derive(Clone, Copy, Debug)]
+struct Scope {
+    name: &'static str,
+    depth: u32,
+    begin_query: u32,
+    end_query: Option<u32>,
+}
+
+#[derive(Default)]
+struct ProfilerState {
+    scopes: Vec<Scope>,
+    open_scopes: Vec<usize>,
+    next_query: u32,
+}
+
+//timestamps of one frame, owned by it so results are only read once its fence is signaled
+pub struct GpuProfiler {
+    //null if the queue does not support timestamps, all scopes are ignored then
+    query_pool: vk::QueryPool,
+    //nanoseconds per tick
+    timestamp_period: f32,
+    timestamp_mask: u64,
+
+    //scopes are recorded while the RenderCtx is borrowed immutably
+    state: RefCell<ProfilerState>,
+
+    device: Arc<Device>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScopeTiming {
+    pub name: &'static str,
+    pub depth: u32,
+    //in the timestamp domain of the device, only meaningful relative to other timings
+    pub start_ms: f64,
+    pub duration_ms: f64,
+}
+
+impl GpuProfiler {
+    pub fn new(
+        device: Arc<Device>,
+        timestamp_period: f32,
+        timestamp_valid_bits: u32,
+    ) -> Result<Self, vk::Result> {
+        let query_pool = if timestamp_valid_bits > 0 {
+            let query_pool_create_info = vk::QueryPoolCreateInfo::default()
+                .query_type(vk::QueryType::TIMESTAMP)
+                .query_count(2 * MAX_SCOPES);
+
+            unsafe { device.create_query_pool(&query_pool_create_info, None)? }
+        } else {
+            vk::QueryPool::null()
+        };
+
+        let timestamp_mask = match timestamp_valid_bits {
+            64.. => u64::MAX,
+            bits => (1 << bits) - 1,
+        };
+
+        Ok(Self {
+            query_pool,
+            timestamp_period,
+            timestamp_mask,
+
+            state: Default::default(),
+
+            device,
+        })
+    }
+
+    //must be recorded before any scope, results of the previous submission are discarded
+    pub unsafe fn reset(&self, command_buffer: vk::CommandBuffer) {
+        *self.state.borrow_mut() = Default::default();
+
+        if self.query_pool != vk::QueryPool::null() {
+            self.device
+                .cmd_reset_query_pool(command_buffer, self.query_pool, 0, 2 * MAX_SCOPES);
+        }
+    }
+
+    pub unsafe fn begin_scope(&self, command_buffer: vk::CommandBuffer, name: &'static str) {
+        let mut state = self.state.borrow_mut();
+        let depth = state.open_scopes.len() as u32;
+        let begin_query = state.next_query;
+
+        let index = state.scopes.len();
+        state.open_scopes.push(index);
+
+        //still tracked when out of queries, so end_scope stays balanced
+        if self.query_pool == vk::QueryPool::null() || begin_query + 2 > 2 * MAX_SCOPES {
+            state.scopes.push(Scope {
+                name,
+                depth,
+                begin_query: u32::MAX,
+                end_query: None,
+            });
+            return;
+        }
+
+        state.next_query += 2;
+        state.scopes.push(Scope {
+            name,
+            depth,
+            begin_query,
+            end_query: None,
+        });
+
+        self.device.cmd_write_timestamp2(
+            command_buffer,
+            vk::PipelineStageFlags2::ALL_COMMANDS,
+            self.query_pool,
+            begin_query,
+        );
+    }
+
+    pub unsafe fn end_scope(&self, command_buffer: vk::CommandBuffer) {
+        let mut state = self.state.borrow_mut();
+        let index = state
+            .open_scopes
+            .pop()
+            .expect("end_scope without begin_scope");
+        let scope = &mut state.scopes[index];
+
+        if scope.begin_query == u32::MAX {
+            return;
+        }
+
+        let end_query = scope.begin_query + 1;
+        scope.end_query = Some(end_query);
+
+        self.device.cmd_write_timestamp2(
+            command_buffer,
+            vk::PipelineStageFlags2::ALL_COMMANDS,
+            self.query_pool,
+            end_query,
+        );
+    }
+
+    //call once the fence of the frame is signaled, returns nothing if no scopes were recorded or the results are not available,
+    //the results of a submission are only returned once, so a frame that is not submitted again is not counted twice
+    pub unsafe fn take_results(&self) -> Vec<ScopeTiming> {
+        let state = mem::take(&mut *self.state.borrow_mut());
+        if state.next_query == 0 {
+            return Vec::new();
+        }
+        debug_assert!(state.open_scopes.is_empty(), "unbalanced profiler scopes");
+
+        let mut timestamps = vec![0u64; state.next_query as usize];
+        if self
+            .device
+            .get_query_pool_results(
+                self.query_pool,
+                0,
+                &mut timestamps,
+                vk::QueryResultFlags::TYPE_64,
+            )
+            .is_err()
+        {
+            return Vec::new();
+        }
+
+        let to_ms =
+            |ticks: u64| (ticks & self.timestamp_mask) as f64 * self.timestamp_period as f64 / 1e6;
+
+        state
+            .scopes
+            .iter()
+            .filter_map(|scope| {
+                let end_query = scope.end_query?;
+                let begin = timestamps[scope.begin_query as usize];
+                let end = timestamps[end_query as usize];
+
+                Some(ScopeTiming {
+                    name: scope.name,
+                    depth: scope.depth,
+                    start_ms: to_ms(begin),
+                    duration_ms: to_ms(end.wrapping_sub(begin)),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Drop for GpuProfiler {
+    fn drop(&mut self) {
+        unsafe {
+            if self.query_pool != vk::QueryPool::null() {
+                self.device.destroy_query_pool(self.query_pool, None);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScopeStats {
+    pub count: u64,
+    pub total_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub last_ms: f64,
+}
+
+impl ScopeStats {
+    pub fn average_ms(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_ms / self.count as f64
+        }
+    }
+}
+
+//scope timings aggregated over all frames, plus the most recent frames for the trace export
+#[derive(Default)]
+pub struct GpuProfile {
+    //in the order the scopes were first seen
+    pub scopes: Vec<(&'static str, ScopeStats)>,
+    trace_frames: VecDeque<Vec<ScopeTiming>>,
+}
+
+impl GpuProfile {
+    pub fn add_frame(&mut self, timings: Vec<ScopeTiming>) {
+        if timings.is_empty() {
+            return;
+        }
+
+        for timing in &timings {
+            let index = match self
+                .scopes
+                .iter()
+                .position(|(name, _)| *name == timing.name)
+            {
+                Some(index) => index,
+                None => {
+                    let stats = ScopeStats {
+                        min_ms: f64::MAX,
+                        ..Default::default()
+                    };
+                    self.scopes.push((timing.name, stats));
+                    self.scopes.len() - 1
+                }
+            };
+
+            let stats = &mut self.scopes[index].1;
+            stats.count += 1;
+            stats.total_ms += timing.duration_ms;
+            stats.min_ms = stats.min_ms.min(timing.duration_ms);
+            stats.max_ms = stats.max_ms.max(timing.duration_ms);
+            stats.last_ms = timing.duration_ms;
+        }
+
+        if self.trace_frames.len() == MAX_TRACE_FRAMES {
+            self.trace_frames.pop_front();
+        }
+        self.trace_frames.push_back(timings);
+    }
+
+    pub fn scope(&self, name: &str) -> Option<&ScopeStats> {
+        self.scopes
+            .iter()
+            .find(|(scope_name, _)| *scope_name == name)
+            .map(|(_, stats)| stats)
+    }
+
+    //chrome://tracing and perfetto json, one complete event per scope of the last MAX_TRACE_FRAMES frames
+    pub fn write_chrome_trace(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "{{\"traceEvents\":[")?;
+
+        let events = self.trace_frames.iter().flatten();
+        for (index, timing) in events.enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+
+            write!(
+                writer,
+                "\n{{\"name\":\"{}\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":0,\"tid\":0,\"args\":{{\"depth\":{}}}}}",
+                escape_json(timing.name),
+                timing.start_ms * 1000.0,
+                timing.duration_ms * 1000.0,
+                timing.depth
+            )?;
+        }
+
+        write!(writer, "\n],\"displayTimeUnit\":\"ms\"}}")?;
+        writer.flush()
+    }
+}
+
+//average over all frames, short enough for a window title
+impl fmt::Display for GpuProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (name, stats)) in self.scopes.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {:.2} ms", name, stats.average_ms())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
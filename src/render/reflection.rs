@@ -0,0 +1,709 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use ash::{vk, Device};
+
+use crate::render::spirv::{HEADER_WORDS, MAGIC};
+
+mod op {
+    pub const NAME: u32 = 5;
+    pub const ENTRY_POINT: u32 = 15;
+    pub const EXECUTION_MODE: u32 = 16;
+    pub const TYPE_INT: u32 = 21;
+    pub const TYPE_FLOAT: u32 = 22;
+    pub const TYPE_VECTOR: u32 = 23;
+    pub const TYPE_MATRIX: u32 = 24;
+    pub const TYPE_IMAGE: u32 = 25;
+    pub const TYPE_SAMPLER: u32 = 26;
+    pub const TYPE_SAMPLED_IMAGE: u32 = 27;
+    pub const TYPE_ARRAY: u32 = 28;
+    pub const TYPE_RUNTIME_ARRAY: u32 = 29;
+    pub const TYPE_STRUCT: u32 = 30;
+    pub const TYPE_POINTER: u32 = 32;
+    pub const CONSTANT: u32 = 43;
+    pub const VARIABLE: u32 = 59;
+    pub const DECORATE: u32 = 71;
+    pub const MEMBER_DECORATE: u32 = 72;
+    pub const EXECUTION_MODE_ID: u32 = 331;
+    pub const TYPE_ACCELERATION_STRUCTURE: u32 = 5341;
+}
+
+mod decoration {
+    pub const BUFFER_BLOCK: u32 = 3;
+    pub const ARRAY_STRIDE: u32 = 6;
+    pub const MATRIX_STRIDE: u32 = 7;
+    pub const BINDING: u32 = 33;
+    pub const DESCRIPTOR_SET: u32 = 34;
+    pub const OFFSET: u32 = 35;
+}
+
+mod storage_class {
+    pub const UNIFORM_CONSTANT: u32 = 0;
+    pub const UNIFORM: u32 = 2;
+    pub const PUSH_CONSTANT: u32 = 9;
+    pub const STORAGE_BUFFER: u32 = 12;
+}
+
+mod execution_mode {
+    pub const LOCAL_SIZE: u32 = 17;
+    pub const OUTPUT_VERTICES: u32 = 26;
+    pub const LOCAL_SIZE_ID: u32 = 38;
+    pub const OUTPUT_PRIMITIVES: u32 = 5270;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DescriptorBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub descriptor_count: u32,
+    pub stage_flags: vk::ShaderStageFlags,
+    //the name of the variable, if the module has debug names
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShaderReflection {
+    pub stage: vk::ShaderStageFlags,
+    pub entry_point: String,
+    pub bindings: Vec<DescriptorBinding>,
+    pub push_constant_range: Option<vk::PushConstantRange>,
+    //only set for compute, task and mesh shaders
+    pub local_size: Option<[u32; 3]>,
+    //only set for mesh shaders
+    pub max_output_vertices: Option<u32>,
+    pub max_output_primitives: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Type {
+    Scalar {
+        size: u32,
+    },
+    Vector {
+        component: u32,
+        count: u32,
+    },
+    Matrix {
+        column: u32,
+        count: u32,
+    },
+    Array {
+        element: u32,
+        length: u32,
+    },
+    RuntimeArray,
+    Struct,
+    Pointer {
+        pointee: u32,
+    },
+    Image {
+        is_buffer: bool,
+        is_subpass: bool,
+        is_storage: bool,
+    },
+    Sampler,
+    SampledImage {
+        is_buffer: bool,
+    },
+    AccelerationStructure,
+}
+
+#[derive(Default)]
+struct Module<'a> {
+    names: HashMap<u32, String>,
+    types: HashMap<u32, Type>,
+    struct_members: HashMap<u32, &'a [u32]>,
+    constants: HashMap<u32, u32>,
+    //(target, decoration) -> first literal
+    decorations: HashMap<(u32, u32), u32>,
+    //(struct, member, decoration) -> first literal
+    member_decorations: HashMap<(u32, u32, u32), u32>,
+    //(id, type, storage class)
+    variables: Vec<(u32, u32, u32)>,
+}
+
+impl<'a> Module<'a> {
+    fn has_decoration(&self, target: u32, decoration: u32) -> bool {
+        self.decorations.contains_key(&(target, decoration))
+    }
+
+    fn get_type(&self, id: u32) -> Result<Type> {
+        self.types
+            .get(&id)
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown type %{}", id))
+    }
+
+    fn constant(&self, id: u32) -> Result<u32> {
+        self.constants
+            .get(&id)
+            .copied()
+            .ok_or_else(|| anyhow!("%{} is not a 32 bit constant", id))
+    }
+
+    //size of a type in an explicitly laid out block, runtime arrays count as empty
+    fn size_of(&self, id: u32) -> Result<u32> {
+        Ok(match self.get_type(id)? {
+            Type::Scalar { size } => size,
+            Type::Vector { component, count } => self.size_of(component)? * count,
+            Type::Matrix { column, count } => self.size_of(column)? * count,
+            Type::Array { element, length } => {
+                let stride = match self.decorations.get(&(id, decoration::ARRAY_STRIDE)) {
+                    Some(&stride) => stride,
+                    None => self.size_of(element)?,
+                };
+                stride * length
+            }
+            Type::RuntimeArray => 0,
+            Type::Struct => {
+                let members = self.struct_members.get(&id).copied().unwrap_or_default();
+                let mut size = 0;
+                for (index, &member) in members.iter().enumerate() {
+                    let index = index as u32;
+                    let offset = self
+                        .member_decorations
+                        .get(&(id, index, decoration::OFFSET))
+                        .copied()
+                        .unwrap_or(size);
+                    let member_size = match (
+                        self.get_type(member)?,
+                        self.member_decorations
+                            .get(&(id, index, decoration::MATRIX_STRIDE)),
+                    ) {
+                        (Type::Matrix { count, .. }, Some(&stride)) => stride * count,
+                        _ => self.size_of(member)?,
+                    };
+                    size = size.max(offset + member_size);
+                }
+                size
+            }
+            t => bail!("%{} ({:?}) has no size", id, t),
+        })
+    }
+
+    fn descriptor_type(&self, storage_class: u32, id: u32) -> Result<vk::DescriptorType> {
+        Ok(match (storage_class, self.get_type(id)?) {
+            (storage_class::UNIFORM, Type::Struct)
+                if self.has_decoration(id, decoration::BUFFER_BLOCK) =>
+            {
+                vk::DescriptorType::STORAGE_BUFFER
+            }
+            (storage_class::UNIFORM, Type::Struct) => vk::DescriptorType::UNIFORM_BUFFER,
+            (storage_class::STORAGE_BUFFER, Type::Struct) => vk::DescriptorType::STORAGE_BUFFER,
+            (storage_class::UNIFORM_CONSTANT, Type::Sampler) => vk::DescriptorType::SAMPLER,
+            (storage_class::UNIFORM_CONSTANT, Type::SampledImage { is_buffer: true }) => {
+                vk::DescriptorType::UNIFORM_TEXEL_BUFFER
+            }
+            (storage_class::UNIFORM_CONSTANT, Type::SampledImage { .. }) => {
+                vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+            }
+            (
+                storage_class::UNIFORM_CONSTANT,
+                Type::Image {
+                    is_subpass: true, ..
+                },
+            ) => vk::DescriptorType::INPUT_ATTACHMENT,
+            (
+                storage_class::UNIFORM_CONSTANT,
+                Type::Image {
+                    is_buffer,
+                    is_storage,
+                    ..
+                },
+            ) => match (is_buffer, is_storage) {
+                (false, false) => vk::DescriptorType::SAMPLED_IMAGE,
+                (false, true) => vk::DescriptorType::STORAGE_IMAGE,
+                (true, false) => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                (true, true) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+            },
+            (storage_class::UNIFORM_CONSTANT, Type::AccelerationStructure) => {
+                vk::DescriptorType::ACCELERATION_STRUCTURE_KHR
+            }
+            (storage_class, t) => {
+                bail!(
+                    "Unsupported descriptor {:?} in storage class {}",
+                    t,
+                    storage_class
+                )
+            }
+        })
+    }
+}
+
+fn execution_model_stage(execution_model: u32) -> Result<vk::ShaderStageFlags> {
+    Ok(match execution_model {
+        0 => vk::ShaderStageFlags::VERTEX,
+        4 => vk::ShaderStageFlags::FRAGMENT,
+        5 => vk::ShaderStageFlags::COMPUTE,
+        5267 => vk::ShaderStageFlags::TASK_NV,
+        5268 => vk::ShaderStageFlags::MESH_NV,
+        5364 => vk::ShaderStageFlags::TASK_EXT,
+        5365 => vk::ShaderStageFlags::MESH_EXT,
+        _ => bail!("Unsupported execution model {}", execution_model),
+    })
+}
+
+//literal strings are nul terminated and padded to whole words, returns the string and the number of words it occupies
+fn parse_string(words: &[u32]) -> Result<(String, usize)> {
+    let mut bytes = Vec::new();
+    for (index, word) in words.iter().enumerate() {
+        for byte in word.to_le_bytes() {
+            if byte == 0 {
+                return Ok((String::from_utf8(bytes)?, index + 1));
+            }
+            bytes.push(byte);
+        }
+    }
+    bail!("Unterminated string")
+}
+
+impl ShaderReflection {
+    //the module must contain exactly one entry point
+    pub fn parse(code: &[u32]) -> Result<Self> {
+        ensure!(code.len() >= HEADER_WORDS, "Too short for a SPIR-V header");
+        ensure!(
+            code[0] == MAGIC,
+            "Invalid SPIR-V magic number {:#010x}",
+            code[0]
+        );
+
+        let mut module = Module::default();
+        let mut entry_point = None;
+        let mut local_size = None;
+        let mut local_size_ids = None;
+        let mut max_output_vertices = None;
+        let mut max_output_primitives = None;
+
+        let mut offset = HEADER_WORDS;
+        while offset < code.len() {
+            let word_count = (code[offset] >> 16) as usize;
+            let opcode = code[offset] & 0xffff;
+            ensure!(
+                word_count > 0 && offset + word_count <= code.len(),
+                "Invalid instruction at word {}",
+                offset
+            );
+            let operands = &code[offset + 1..offset + word_count];
+            let operand = |index: usize| {
+                operands.get(index).copied().ok_or_else(|| {
+                    anyhow!(
+                        "Instruction {} at word {} is missing operand {}",
+                        opcode,
+                        offset,
+                        index
+                    )
+                })
+            };
+
+            match opcode {
+                op::NAME => {
+                    module
+                        .names
+                        .insert(operand(0)?, parse_string(&operands[1..])?.0);
+                }
+                op::ENTRY_POINT => {
+                    ensure!(entry_point.is_none(), "Multiple entry points");
+                    let stage = execution_model_stage(operand(0)?)?;
+                    let name_operands = operands.get(2..).ok_or_else(|| {
+                        anyhow!("Entry point at word {} is missing its name", offset)
+                    })?;
+                    let (name, _) = parse_string(name_operands)?;
+                    entry_point = Some((stage, name));
+                }
+                op::EXECUTION_MODE | op::EXECUTION_MODE_ID => match operand(1)? {
+                    execution_mode::LOCAL_SIZE => {
+                        local_size = Some([operand(2)?, operand(3)?, operand(4)?])
+                    }
+                    execution_mode::LOCAL_SIZE_ID => {
+                        local_size_ids = Some([operand(2)?, operand(3)?, operand(4)?])
+                    }
+                    execution_mode::OUTPUT_VERTICES => max_output_vertices = Some(operand(2)?),
+                    execution_mode::OUTPUT_PRIMITIVES => max_output_primitives = Some(operand(2)?),
+                    _ => {}
+                },
+                op::TYPE_INT | op::TYPE_FLOAT => {
+                    module.types.insert(
+                        operand(0)?,
+                        Type::Scalar {
+                            size: operand(1)? / 8,
+                        },
+                    );
+                }
+                op::TYPE_VECTOR => {
+                    module.types.insert(
+                        operand(0)?,
+                        Type::Vector {
+                            component: operand(1)?,
+                            count: operand(2)?,
+                        },
+                    );
+                }
+                op::TYPE_MATRIX => {
+                    module.types.insert(
+                        operand(0)?,
+                        Type::Matrix {
+                            column: operand(1)?,
+                            count: operand(2)?,
+                        },
+                    );
+                }
+                op::TYPE_IMAGE => {
+                    //dim 5 is Buffer and 6 SubpassData, sampled 2 means storage image
+                    module.types.insert(
+                        operand(0)?,
+                        Type::Image {
+                            is_buffer: operand(2)? == 5,
+                            is_subpass: operand(2)? == 6,
+                            is_storage: operand(6)? == 2,
+                        },
+                    );
+                }
+                op::TYPE_SAMPLER => {
+                    module.types.insert(operand(0)?, Type::Sampler);
+                }
+                op::TYPE_SAMPLED_IMAGE => {
+                    let is_buffer = matches!(
+                        module.get_type(operand(1)?)?,
+                        Type::Image {
+                            is_buffer: true,
+                            ..
+                        }
+                    );
+                    module
+                        .types
+                        .insert(operand(0)?, Type::SampledImage { is_buffer });
+                }
+                op::TYPE_ARRAY => {
+                    module.types.insert(
+                        operand(0)?,
+                        Type::Array {
+                            element: operand(1)?,
+                            length: module.constant(operand(2)?)?,
+                        },
+                    );
+                }
+                op::TYPE_RUNTIME_ARRAY => {
+                    module.types.insert(operand(0)?, Type::RuntimeArray);
+                }
+                op::TYPE_STRUCT => {
+                    module.types.insert(operand(0)?, Type::Struct);
+                    module.struct_members.insert(operand(0)?, &operands[1..]);
+                }
+                op::TYPE_POINTER => {
+                    module.types.insert(
+                        operand(0)?,
+                        Type::Pointer {
+                            pointee: operand(2)?,
+                        },
+                    );
+                }
+                op::TYPE_ACCELERATION_STRUCTURE => {
+                    module
+                        .types
+                        .insert(operand(0)?, Type::AccelerationStructure);
+                }
+                //wider constants are never array lengths or workgroup sizes
+                op::CONSTANT if operands.len() == 3 => {
+                    module.constants.insert(operand(1)?, operand(2)?);
+                }
+                op::VARIABLE => {
+                    module
+                        .variables
+                        .push((operand(1)?, operand(0)?, operand(2)?));
+                }
+                op::DECORATE => {
+                    module.decorations.insert(
+                        (operand(0)?, operand(1)?),
+                        operands.get(2).copied().unwrap_or_default(),
+                    );
+                }
+                op::MEMBER_DECORATE => {
+                    module.member_decorations.insert(
+                        (operand(0)?, operand(1)?, operand(2)?),
+                        operands.get(3).copied().unwrap_or_default(),
+                    );
+                }
+                _ => {}
+            }
+
+            offset += word_count;
+        }
+
+        let (stage, entry_point) = entry_point.ok_or_else(|| anyhow!("No entry point"))?;
+
+        if let Some(ids) = local_size_ids {
+            local_size = Some([
+                module.constant(ids[0])?,
+                module.constant(ids[1])?,
+                module.constant(ids[2])?,
+            ]);
+        }
+
+        let mut bindings = Vec::new();
+        let mut push_constant_range = None;
+
+        for &(id, pointer_type, storage_class) in &module.variables {
+            let name = module.names.get(&id).cloned();
+            let context = || {
+                format!(
+                    "variable {}",
+                    name.as_deref().unwrap_or(&format!("%{}", id))
+                )
+            };
+
+            let pointee = match module.get_type(pointer_type).with_context(context)? {
+                Type::Pointer { pointee } => pointee,
+                t => bail!("The type of {} is {:?} instead of a pointer", context(), t),
+            };
+
+            match storage_class {
+                storage_class::PUSH_CONSTANT => {
+                    ensure!(
+                        push_constant_range.is_none(),
+                        "Multiple push constant blocks"
+                    );
+                    push_constant_range = Some(
+                        vk::PushConstantRange::default()
+                            .stage_flags(stage)
+                            .size(module.size_of(pointee).with_context(context)?),
+                    );
+                }
+                storage_class::UNIFORM_CONSTANT
+                | storage_class::UNIFORM
+                | storage_class::STORAGE_BUFFER => {
+                    let (set, binding) = match (
+                        module.decorations.get(&(id, decoration::DESCRIPTOR_SET)),
+                        module.decorations.get(&(id, decoration::BINDING)),
+                    ) {
+                        (Some(&set), Some(&binding)) => (set, binding),
+                        _ => bail!("{} has no descriptor set or binding", context()),
+                    };
+
+                    let (descriptor_type, descriptor_count) = match module.get_type(pointee)? {
+                        Type::Array { element, length } => {
+                            (module.descriptor_type(storage_class, element), length)
+                        }
+                        Type::RuntimeArray => {
+                            bail!("{} is a runtime array, which is not supported", context())
+                        }
+                        _ => (module.descriptor_type(storage_class, pointee), 1),
+                    };
+
+                    bindings.push(DescriptorBinding {
+                        set,
+                        binding,
+                        descriptor_type: descriptor_type.with_context(context)?,
+                        descriptor_count,
+                        stage_flags: stage,
+                        name,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        bindings.sort_by_key(|binding| (binding.set, binding.binding));
+
+        Ok(Self {
+            stage,
+            entry_point,
+            bindings,
+            push_constant_range,
+            local_size,
+            max_output_vertices,
+            max_output_primitives,
+        })
+    }
+}
+
+//the interface of all stages of a pipeline
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PipelineReflection {
+    //bindings of each set, sorted by binding
+    pub sets: BTreeMap<u32, Vec<DescriptorBinding>>,
+    pub push_constant_ranges: Vec<vk::PushConstantRange>,
+}
+
+//bindings used by several stages must agree on their type and count
+pub fn merge(stages: &[ShaderReflection]) -> Result<PipelineReflection> {
+    let mut sets: BTreeMap<u32, Vec<DescriptorBinding>> = BTreeMap::new();
+
+    for stage in stages {
+        for binding in &stage.bindings {
+            let bindings = sets.entry(binding.set).or_default();
+
+            match bindings.iter_mut().find(|b| b.binding == binding.binding) {
+                Some(existing) => {
+                    ensure!(
+                        existing.descriptor_type == binding.descriptor_type
+                            && existing.descriptor_count == binding.descriptor_count,
+                        "Binding {}.{} is {} {:?} in {:?} but {} {:?} in {:?}",
+                        binding.set,
+                        binding.binding,
+                        existing.descriptor_count,
+                        existing.descriptor_type,
+                        existing.stage_flags,
+                        binding.descriptor_count,
+                        binding.descriptor_type,
+                        stage.stage
+                    );
+                    existing.stage_flags |= binding.stage_flags;
+                    if existing.name.is_none() {
+                        existing.name = binding.name.clone();
+                    }
+                }
+                None => bindings.push(binding.clone()),
+            }
+        }
+    }
+
+    sets.values_mut()
+        .for_each(|bindings| bindings.sort_by_key(|binding| binding.binding));
+
+    //a single range covering every stage's block is valid as long as each stage only reads its own part
+    let push_constant_range = stages
+        .iter()
+        .filter_map(|stage| stage.push_constant_range)
+        .reduce(|a, b| {
+            let offset = a.offset.min(b.offset);
+            let end = (a.offset + a.size).max(b.offset + b.size);
+            vk::PushConstantRange::default()
+                .stage_flags(a.stage_flags | b.stage_flags)
+                .offset(offset)
+                .size(end - offset)
+        });
+
+    Ok(PipelineReflection {
+        sets,
+        push_constant_ranges: push_constant_range.into_iter().collect(),
+    })
+}
+
+impl PipelineReflection {
+    pub fn binding(&self, set: u32, binding: u32) -> Option<&DescriptorBinding> {
+        self.sets
+            .get(&set)?
+            .iter()
+            .find(|descriptor_binding| descriptor_binding.binding == binding)
+    }
+
+    //whether a pipeline with the interface other can use a layout created from self,
+    //everything other uses has to be declared for at least its stages, names are ignored
+    pub fn ensure_layout_compatible(&self, other: &PipelineReflection) -> Result<()> {
+        for binding in other.sets.values().flatten() {
+            let existing = self.binding(binding.set, binding.binding).ok_or_else(|| {
+                anyhow!(
+                    "Binding {}.{} is not part of the pipeline layout",
+                    binding.set,
+                    binding.binding
+                )
+            })?;
+            ensure!(
+                existing.descriptor_type == binding.descriptor_type
+                    && existing.descriptor_count == binding.descriptor_count
+                    && existing.stage_flags.contains(binding.stage_flags),
+                "Binding {}.{} is {} {:?} in {:?} but the pipeline layout has {} {:?} in {:?}",
+                binding.set,
+                binding.binding,
+                binding.descriptor_count,
+                binding.descriptor_type,
+                binding.stage_flags,
+                existing.descriptor_count,
+                existing.descriptor_type,
+                existing.stage_flags
+            );
+        }
+
+        for range in &other.push_constant_ranges {
+            ensure!(
+                self.push_constant_ranges.iter().any(|existing| {
+                    existing.stage_flags.contains(range.stage_flags)
+                        && existing.offset <= range.offset
+                        && range.offset + range.size <= existing.offset + existing.size
+                }),
+                "Push constants {}..{} in {:?} are not covered by the pipeline layout",
+                range.offset,
+                range.offset + range.size,
+                range.stage_flags
+            );
+        }
+
+        Ok(())
+    }
+
+    //one pool size per descriptor type of the set
+    pub fn descriptor_pool_sizes(&self, set: u32) -> Vec<vk::DescriptorPoolSize> {
+        let mut pool_sizes: Vec<vk::DescriptorPoolSize> = Vec::new();
+
+        for binding in self.sets.get(&set).into_iter().flatten() {
+            match pool_sizes
+                .iter_mut()
+                .find(|pool_size| pool_size.ty == binding.descriptor_type)
+            {
+                Some(pool_size) => pool_size.descriptor_count += binding.descriptor_count,
+                None => pool_sizes.push(
+                    vk::DescriptorPoolSize::default()
+                        .ty(binding.descriptor_type)
+                        .descriptor_count(binding.descriptor_count),
+                ),
+            }
+        }
+
+        pool_sizes
+    }
+
+    //one layout for every set up to the highest one used, unused sets get empty layouts
+    pub unsafe fn create_descriptor_set_layouts(
+        &self,
+        device: &Device,
+    ) -> Result<Vec<vk::DescriptorSetLayout>> {
+        let set_count = self.sets.keys().next_back().map_or(0, |&set| set + 1);
+
+        let mut descriptor_set_layouts = Vec::with_capacity(set_count as usize);
+        for set in 0..set_count {
+            let descriptor_set_layout_bindings: Vec<_> = self
+                .sets
+                .get(&set)
+                .into_iter()
+                .flatten()
+                .map(|binding| {
+                    vk::DescriptorSetLayoutBinding::default()
+                        .binding(binding.binding)
+                        .descriptor_type(binding.descriptor_type)
+                        .descriptor_count(binding.descriptor_count)
+                        .stage_flags(binding.stage_flags)
+                })
+                .collect();
+
+            let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo::default()
+                .bindings(&descriptor_set_layout_bindings);
+
+            match device.create_descriptor_set_layout(&descriptor_set_layout_create_info, None) {
+                Ok(descriptor_set_layout) => descriptor_set_layouts.push(descriptor_set_layout),
+                Err(result) => {
+                    descriptor_set_layouts
+                        .into_iter()
+                        .for_each(|descriptor_set_layout| {
+                            device.destroy_descriptor_set_layout(descriptor_set_layout, None)
+                        });
+                    return Err(result.into());
+                }
+            }
+        }
+
+        Ok(descriptor_set_layouts)
+    }
+
+    pub unsafe fn create_pipeline_layout(
+        &self,
+        device: &Device,
+        descriptor_set_layouts: &[vk::DescriptorSetLayout],
+    ) -> Result<vk::PipelineLayout> {
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::default()
+            .set_layouts(descriptor_set_layouts)
+            .push_constant_ranges(&self.push_constant_ranges);
+
+        Ok(device.create_pipeline_layout(&pipeline_layout_create_info, None)?)
+    }
+}
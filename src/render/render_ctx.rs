@@ -1,9 +1,10 @@
-use std::{mem::ManuallyDrop, slice, sync::Arc};
+use std::{borrow::Cow, ffi::CStr, mem::ManuallyDrop, slice, sync::Arc};
 
+use anyhow::{ensure, Context};
 use ash::{
     extensions::{
+        ext::DebugUtils,
         khr::{Surface, Swapchain},
-        nv::MeshShader,
     },
     vk, Device, Entry, Instance,
 };
@@ -12,27 +13,71 @@ use vk_mem::{Allocation, Allocator, AllocatorCreateInfo};
 
 use winit::window::Window;
 
-use crate::render::{frame, frame::Frame, util};
+use crate::{
+    asset::{scene::Scene, Mesh},
+    render::{
+        cleanup::Cleanup,
+        config::{self, RenderConfig},
+        debug_utils::{DebugMessenger, DebugNames, VALIDATION_LAYER},
+        device_selection,
+        error::RenderCtxError,
+        frame::Frame,
+        geometry,
+        geometry::Geometry,
+        mesh_shader::{MeshShaderLoader, MeshShaderVariant},
+        meshlet,
+        offscreen::OffscreenTarget,
+        output::{self, OutputPass, OutputTransform},
+        pipeline_cache,
+        pipeline_cache::PipelineCache,
+        pipeline_statistics,
+        pipeline_statistics::PipelineStatistics,
+        profiler::GpuProfile,
+        reflection,
+        reflection::{PipelineReflection, ShaderReflection},
+        shaders, spirv,
+        spirv::SpirvVersion,
+        util,
+    },
+};
 
-pub const WIDTH: u32 = 1600;
-pub const HEIGHT: u32 = 900;
-pub const SWAPCHAIN_FORMAT: vk::Format = vk::Format::B8G8R8A8_UNORM;
+//headless contexts always output sdr, read_rgba8 expects this layout
+pub const OFFSCREEN_FORMAT: vk::Format = vk::Format::B8G8R8A8_SRGB;
 pub const DEPTH_FORMAT: vk::Format = vk::Format::D32_SFLOAT;
 
+pub const VERTEX_SHADER_PATH: &str = "example.vert.spv";
+pub const FRAGMENT_SHADER_PATH: &str = "example.frag.spv";
+
 pub struct RenderCtx {
     pub entry_loader: Entry,
 
     pub instance_loader: Instance,
     pub surface_loader: Surface,
+    //None unless validation was requested and is available
+    pub debug_messenger: Option<DebugMessenger>,
+    //enabled together with the debug messenger
+    pub debug_names: DebugNames,
 
+    //null for headless contexts
     pub surface: vk::SurfaceKHR,
 
+    pub physical_device: vk::PhysicalDevice,
     pub device_loader: Arc<Device>,
     pub swapchain_loader: Swapchain,
-    pub mesh_shader_loader: MeshShader,
+    //None if the vertex shader fallback is used
+    pub mesh_shader_loader: Option<MeshShaderLoader>,
 
+    pub direct_queue_family_index: u32,
     pub direct_queue: vk::Queue,
     pub swapchain: vk::SwapchainKHR,
+    //the extent of the offscreen target for headless contexts
+    pub swapchain_extent: vk::Extent2D,
+    //set when presenting reports an out of date or suboptimal swapchain, cleared by recreate_swapchain
+    pub swapchain_out_of_date: bool,
+    //of the swapchain images or the offscreen target, chosen together with the output transform
+    pub surface_format: vk::SurfaceFormatKHR,
+    //chosen once from the configured mode and what the surface supports, FIFO for headless contexts
+    pub present_mode: vk::PresentModeKHR,
 
     pub depth_image: vk::Image,
     pub depth_image_allocation: Allocation,
@@ -41,53 +86,197 @@ pub struct RenderCtx {
     pub swapchain_images: Vec<vk::Image>,
     pub swapchain_image_views: Vec<vk::ImageView>,
 
+    //Some for headless contexts, which render into it instead of a swapchain
+    pub offscreen_target: Option<OffscreenTarget>,
+
+    //newest spir-v the device accepts, shaders are checked against it before module creation
+    pub max_spirv_version: SpirvVersion,
+    //derived from the shaders of the pipeline
+    pub pipeline_reflection: PipelineReflection,
     pub descriptor_set_layout: vk::DescriptorSetLayout,
     pub pipeline_layout: vk::PipelineLayout,
+    //saved to disk when the context is dropped
+    pub pipeline_cache: ManuallyDrop<PipelineCache>,
     pub pipeline: vk::Pipeline,
+    //the mesh pass renders into its scene color, which it then writes to the swapchain image or offscreen target
+    pub output_pass: ManuallyDrop<OutputPass>,
 
+    //one per frame in flight, see set_frames_in_flight
     pub frames: Vec<ManuallyDrop<Frame>>,
+    timestamp_period: f32,
+    timestamp_valid_bits: u32,
+    pipeline_statistic_flags: vk::QueryPipelineStatisticFlags,
+    //timings of the scopes recorded by the renderer, aggregated over all presented frames
+    pub gpu_profile: GpuProfile,
+    //off by default, has no effect if the device does not support pipeline statistics
+    pub pipeline_statistics_enabled: bool,
+    pub(crate) last_pipeline_statistics: Option<PipelineStatistics>,
+
+    pub geometry: Option<Geometry>,
 
     pub allocator: ManuallyDrop<Arc<Allocator>>,
 }
 
 impl RenderCtx {
-    pub fn new(window: &Window) -> Self {
+    pub fn new(window: &Window, config: &RenderConfig) -> Result<Self, RenderCtxError> {
+        Self::create(Some(window), vk::Extent2D::default(), config)
+    }
+
+    //renders into an offscreen target instead of a swapchain, no window or display is required
+    pub fn new_headless(
+        width: u32,
+        height: u32,
+        config: &RenderConfig,
+    ) -> Result<Self, RenderCtxError> {
+        Self::create(None, vk::Extent2D { width, height }, config)
+    }
+
+    //offscreen_extent is only used without a window, everything created before a failing step is destroyed again
+    fn create(
+        window: Option<&Window>,
+        offscreen_extent: vk::Extent2D,
+        config: &RenderConfig,
+    ) -> Result<Self, RenderCtxError> {
+        check_frames_in_flight(config.frames_in_flight)?;
+
         unsafe {
-            let entry_loader = Entry::load().unwrap();
+            let entry_loader = Entry::load().map_err(RenderCtxError::Loader)?;
 
-            let application_info = vk::ApplicationInfo::default().api_version(vk::API_VERSION_1_3);
+            //declared before everything but the entry, so it runs after every other local holding the device or allocator is dropped
+            let mut cleanup = Cleanup::new();
 
-            let instance_layers = [b"VK_LAYER_KHRONOS_validation\0".as_ptr().cast()];
+            let application_info = vk::ApplicationInfo::default().api_version(vk::API_VERSION_1_3);
 
+            let mut instance_layers = vec![];
             let mut instance_extensions = vec![];
-            ash_window::enumerate_required_extensions(&window)
-                .unwrap()
+            if let Some(window) = window {
+                ash_window::enumerate_required_extensions(&window)
+                    .map_err(RenderCtxError::surface("enumerate the instance extensions"))?
+                    .iter()
+                    .for_each(|e| instance_extensions.push(CStr::from_ptr(*e)));
+            }
+
+            //object names and labels show up in tools like renderdoc, so they do not depend on validation
+            let debug_utils = is_instance_extension_available(&entry_loader, DebugUtils::name())?;
+            if debug_utils {
+                instance_extensions.push(DebugUtils::name());
+            }
+
+            //machines without the sdk simply run without validation
+            let validation_layer = CStr::from_bytes_with_nul_unchecked(VALIDATION_LAYER);
+            let validation = config.validation
+                && debug_utils
+                && is_instance_layer_available(&entry_loader, validation_layer)?;
+            if validation {
+                instance_layers.push(validation_layer);
+            } else if config.validation {
+                log::warn!(
+                    "{} or {} is not available, continuing without validation",
+                    validation_layer.to_string_lossy(),
+                    DebugUtils::name().to_string_lossy()
+                );
+            }
+
+            //without it surfaces only report the srgb color space
+            let swapchain_colorspace = vk::ExtSwapchainColorspaceFn::name();
+            if window.is_some() && config.hdr_output.is_some() {
+                if is_instance_extension_available(&entry_loader, swapchain_colorspace)? {
+                    instance_extensions.push(swapchain_colorspace);
+                } else {
+                    log::warn!(
+                        "{} is not available, hdr output is disabled",
+                        swapchain_colorspace.to_string_lossy()
+                    );
+                }
+            }
+
+            check_instance_support(&entry_loader, &instance_layers, &instance_extensions)?;
+
+            let instance_layer_names: Vec<_> =
+                instance_layers.iter().map(|layer| layer.as_ptr()).collect();
+            let instance_extension_names: Vec<_> = instance_extensions
                 .iter()
-                .for_each(|e| instance_extensions.push(*e));
+                .map(|extension| extension.as_ptr())
+                .collect();
 
             let instance_create_info = vk::InstanceCreateInfo::default()
-                .enabled_layer_names(&instance_layers)
-                .enabled_extension_names(&instance_extensions)
+                .enabled_layer_names(&instance_layer_names)
+                .enabled_extension_names(&instance_extension_names)
                 .application_info(&application_info);
 
             let instance_loader = entry_loader
                 .create_instance(&instance_create_info, None)
-                .unwrap();
+                .map_err(RenderCtxError::vulkan("create the instance"))?;
+            cleanup.push({
+                let instance_loader = instance_loader.clone();
+                move || instance_loader.destroy_instance(None)
+            });
             let surface_loader = Surface::new(&entry_loader, &instance_loader);
 
-            let surface =
-                ash_window::create_surface(&entry_loader, &instance_loader, &window, None).unwrap();
+            //a local instead of a cleanup action, it is destroyed before the instance either way
+            let debug_messenger = if validation {
+                Some(
+                    DebugMessenger::new(
+                        &entry_loader,
+                        &instance_loader,
+                        config.panic_on_validation_error,
+                    )
+                    .map_err(RenderCtxError::vulkan("create the debug messenger"))?,
+                )
+            } else {
+                None
+            };
 
-            let physical_devices = instance_loader.enumerate_physical_devices().unwrap();
-            let physical_device = physical_devices[0];
+            let surface = match window {
+                Some(window) => {
+                    ash_window::create_surface(&entry_loader, &instance_loader, &window, None)
+                        .map_err(RenderCtxError::surface("create the surface"))?
+                }
+                None => vk::SurfaceKHR::null(),
+            };
+            if surface != vk::SurfaceKHR::null() {
+                let surface_loader = surface_loader.clone();
+                cleanup.push(move || surface_loader.destroy_surface(surface, None));
+            }
+
+            let mut required_device_extensions = vec![];
+            if window.is_some() {
+                required_device_extensions.push(Swapchain::name());
+            }
+
+            let selected_device = device_selection::select_physical_device(
+                &instance_loader,
+                &surface_loader,
+                window.map(|_| surface),
+                &required_device_extensions,
+                config.device.as_ref(),
+            )
+            .map_err(|e| match e.downcast_ref::<vk::Result>() {
+                Some(&result) => RenderCtxError::vulkan("select a physical device")(result),
+                None => RenderCtxError::MissingDeviceFeature(format!("{:#}", e)),
+            })?;
+            let physical_device = selected_device.physical_device;
+            let direct_queue_family_index = selected_device.queue_family_index;
+            let mesh_shader_variant = selected_device.mesh_shader_variant;
+            let optional_features = selected_device.optional_features;
+            let max_spirv_version =
+                SpirvVersion::max_supported(selected_device.properties.api_version);
 
             let queue_priority = 1.0;
             let device_queue_create_info = vk::DeviceQueueCreateInfo::default()
+                .queue_family_index(direct_queue_family_index)
                 .queue_priorities(slice::from_ref(&queue_priority));
 
-            let device_extensions = [Swapchain::name().as_ptr(), MeshShader::name().as_ptr()];
+            let mut device_extensions: Vec<_> = required_device_extensions
+                .iter()
+                .map(|extension| extension.as_ptr())
+                .collect();
+            if let Some(mesh_shader_variant) = mesh_shader_variant {
+                device_extensions.push(mesh_shader_variant.extension_name().as_ptr());
+            }
 
-            let physical_device_features = vk::PhysicalDeviceFeatures::default();
+            let physical_device_features = vk::PhysicalDeviceFeatures::default()
+                .pipeline_statistics_query(optional_features.pipeline_statistics_query);
 
             let mut physical_device_dynamic_rendering_features =
                 vk::PhysicalDeviceDynamicRenderingFeatures::default().dynamic_rendering(true);
@@ -96,16 +285,29 @@ impl RenderCtx {
             let mut physical_device_synchronization2_features =
                 vk::PhysicalDeviceSynchronization2Features::default().synchronization2(true);
 
-            let mut physical_device_mesh_shader_features =
+            let mut physical_device_mesh_shader_features_ext =
+                vk::PhysicalDeviceMeshShaderFeaturesEXT::default()
+                    .task_shader(true)
+                    .mesh_shader(true)
+                    .mesh_shader_queries(optional_features.mesh_shader_queries);
+
+            let mut physical_device_mesh_shader_features_nv =
                 vk::PhysicalDeviceMeshShaderFeaturesNV::default()
                     .task_shader(true)
                     .mesh_shader(true);
 
             let mut physical_device_features = vk::PhysicalDeviceFeatures2::default()
                 .features(physical_device_features)
-                .push_next(&mut physical_device_mesh_shader_features)
                 .push_next(&mut physical_device_dynamic_rendering_features)
                 .push_next(&mut physical_device_synchronization2_features);
+            physical_device_features = match mesh_shader_variant {
+                Some(MeshShaderVariant::Ext) => physical_device_features
+                    .push_next(&mut physical_device_mesh_shader_features_ext),
+                Some(MeshShaderVariant::Nv) => {
+                    physical_device_features.push_next(&mut physical_device_mesh_shader_features_nv)
+                }
+                None => physical_device_features,
+            };
 
             let device_create_info = vk::DeviceCreateInfo::default()
                 .push_next(&mut physical_device_features)
@@ -114,10 +316,19 @@ impl RenderCtx {
             let device_loader = Arc::new(
                 instance_loader
                     .create_device(physical_device, &device_create_info, None)
-                    .unwrap(),
+                    .map_err(RenderCtxError::vulkan("create the device"))?,
             );
+            cleanup.push({
+                let device_loader = device_loader.clone();
+                move || device_loader.destroy_device(None)
+            });
             let swapchain_loader = Swapchain::new(&instance_loader, &device_loader);
-            let mesh_shader_loader = MeshShader::new(&instance_loader, &device_loader);
+            let debug_names = DebugNames::new(
+                debug_utils.then(|| DebugUtils::new(&entry_loader, &instance_loader)),
+                device_loader.handle(),
+            );
+            let mesh_shader_loader = mesh_shader_variant
+                .map(|variant| MeshShaderLoader::new(variant, &instance_loader, &device_loader));
 
             let allocator = Arc::new(
                 Allocator::new(AllocatorCreateInfo::new(
@@ -125,116 +336,251 @@ impl RenderCtx {
                     &device_loader,
                     &physical_device,
                 ))
-                .unwrap(),
+                .map_err(RenderCtxError::vulkan("create the memory allocator"))?,
             );
-            let direct_queue = device_loader.get_device_queue(0, 0);
-
-            let swapchian_create_info = vk::SwapchainCreateInfoKHR::default()
-                .surface(surface)
-                .min_image_count(2)
-                .image_format(SWAPCHAIN_FORMAT)
-                .image_color_space(vk::ColorSpaceKHR::SRGB_NONLINEAR)
-                .image_extent(vk::Extent2D {
-                    width: WIDTH,
-                    height: HEIGHT,
-                })
-                .image_array_layers(1)
-                .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
-                .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
-                .pre_transform(vk::SurfaceTransformFlagsKHR::IDENTITY)
-                .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
-                .present_mode(vk::PresentModeKHR::FIFO);
-
-            let swapchain = swapchain_loader
-                .create_swapchain(&swapchian_create_info, None)
-                .unwrap();
-            let swapchain_images = swapchain_loader.get_swapchain_images(swapchain).unwrap();
-
-            let (depth_image, depth_image_allocation, depth_image_view) =
-                util::create_depth_image(&device_loader, &allocator, WIDTH, HEIGHT, DEPTH_FORMAT)
-                    .unwrap();
-
-            let swapchain_image_views = swapchain_images
-                .iter()
-                .map(|image| {
-                    let image_view_create_info = vk::ImageViewCreateInfo::default()
-                        .image(*image)
-                        .view_type(vk::ImageViewType::TYPE_2D)
-                        .format(SWAPCHAIN_FORMAT)
-                        .components(Default::default())
-                        .subresource_range(
-                            vk::ImageSubresourceRange::default()
-                                .aspect_mask(vk::ImageAspectFlags::COLOR)
-                                .level_count(1)
-                                .layer_count(1),
+            let direct_queue = device_loader.get_device_queue(direct_queue_family_index, 0);
+
+            let present_mode = match window {
+                Some(_) => {
+                    let supported_present_modes = surface_loader
+                        .get_physical_device_surface_present_modes(physical_device, surface)
+                        .map_err(RenderCtxError::surface("query the present modes"))?;
+
+                    let present_mode =
+                        config::choose_present_mode(config.present_mode, &supported_present_modes);
+                    if present_mode != config.present_mode.to_vk() {
+                        log::warn!(
+                            "Present mode {} is not supported, using {:?} instead",
+                            config.present_mode,
+                            present_mode
                         );
+                    }
+                    present_mode
+                }
+                None => vk::PresentModeKHR::FIFO,
+            };
 
-                    device_loader.create_image_view(&image_view_create_info, None)
-                })
-                .collect::<Result<Vec<_>, _>>()
-                .unwrap();
-
-            let mesh_shader =
-                util::create_shader_module(&device_loader, "example.mesh.spv").unwrap();
-            let fragment_shader =
-                util::create_shader_module(&device_loader, "example.frag.spv").unwrap();
-
-            let descriptor_set_layout_binding = vk::DescriptorSetLayoutBinding::default()
-                .descriptor_count(1)
-                .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
-                .stage_flags(vk::ShaderStageFlags::MESH_NV);
-
-            let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo::default()
-                .bindings(slice::from_ref(&descriptor_set_layout_binding));
-            let descriptor_set_layout = device_loader
-                .create_descriptor_set_layout(&descriptor_set_layout_create_info, None)
-                .unwrap();
-
-            let pipeline_layout = device_loader
-                .create_pipeline_layout(
-                    &vk::PipelineLayoutCreateInfo::default().set_layouts(&[descriptor_set_layout]),
-                    None,
-                )
-                .unwrap();
-            let pipeline = util::create_mesh_pipeline(
+            let (surface_format, output_transform) = match window {
+                Some(_) => {
+                    let supported_surface_formats = surface_loader
+                        .get_physical_device_surface_formats(physical_device, surface)
+                        .map_err(RenderCtxError::surface("query the formats"))?;
+
+                    let (surface_format, output_transform) = output::choose_surface_format(
+                        &supported_surface_formats,
+                        config.hdr_output,
+                    )
+                    .map_err(RenderCtxError::other("choose a surface format"))?;
+                    if let Some(hdr_output) = config.hdr_output {
+                        if !output_transform.is_hdr() {
+                            log::warn!("{} output is not supported, using sdr instead", hdr_output);
+                        }
+                    }
+                    (surface_format, output_transform)
+                }
+                None => (
+                    vk::SurfaceFormatKHR::default()
+                        .format(OFFSCREEN_FORMAT)
+                        .color_space(vk::ColorSpaceKHR::SRGB_NONLINEAR),
+                    OutputTransform::Srgb,
+                ),
+            };
+            log::info!(
+                "Output format {:?} in {:?}, transform {:?}",
+                surface_format.format,
+                surface_format.color_space,
+                output_transform
+            );
+
+            let (swapchain, swapchain_extent, swapchain_images, swapchain_image_views) =
+                match window {
+                    Some(window) => {
+                        let surface_capabilities = surface_loader
+                            .get_physical_device_surface_capabilities(physical_device, surface)
+                            .map_err(RenderCtxError::surface("query the capabilities"))?;
+
+                        //physical pixels, so hidpi windows get a full resolution swapchain
+                        let window_size = window.inner_size();
+                        let swapchain_extent = choose_swapchain_extent(
+                            &surface_capabilities,
+                            window_size.width,
+                            window_size.height,
+                        );
+
+                        let (swapchain, swapchain_images, swapchain_image_views) =
+                            util::create_swapchain(
+                                &device_loader,
+                                &swapchain_loader,
+                                surface,
+                                swapchain_extent,
+                                surface_format,
+                                present_mode,
+                                config::choose_image_count(&surface_capabilities),
+                                vk::SwapchainKHR::null(),
+                            )
+                            .map_err(RenderCtxError::other("create the swapchain"))?;
+
+                        cleanup.push({
+                            let device_loader = device_loader.clone();
+                            let swapchain_loader = swapchain_loader.clone();
+                            let swapchain_image_views = swapchain_image_views.clone();
+                            move || {
+                                swapchain_image_views.into_iter().for_each(|image_view| {
+                                    device_loader.destroy_image_view(image_view, None)
+                                });
+                                swapchain_loader.destroy_swapchain(swapchain, None);
+                            }
+                        });
+
+                        (
+                            swapchain,
+                            swapchain_extent,
+                            swapchain_images,
+                            swapchain_image_views,
+                        )
+                    }
+                    None => (vk::SwapchainKHR::null(), offscreen_extent, vec![], vec![]),
+                };
+
+            let offscreen_target = match window {
+                Some(_) => None,
+                None => Some(
+                    OffscreenTarget::new(
+                        device_loader.clone(),
+                        allocator.clone(),
+                        offscreen_extent,
+                    )
+                    .map_err(RenderCtxError::other("create the offscreen target"))?,
+                ),
+            };
+
+            let (depth_image, depth_image_allocation, depth_image_view) = util::create_depth_image(
+                &device_loader,
+                &allocator,
+                swapchain_extent.width,
+                swapchain_extent.height,
+                DEPTH_FORMAT,
+            )
+            .map_err(RenderCtxError::other("create the depth image"))?;
+            name_swapchain_resources(&debug_names, &swapchain_images, depth_image);
+            if let Some(offscreen_target) = &offscreen_target {
+                debug_names.set_object_name(offscreen_target.image, "offscreen target");
+            }
+            cleanup.push({
+                let device_loader = device_loader.clone();
+                let allocator = allocator.clone();
+                move || {
+                    device_loader.destroy_image_view(depth_image_view, None);
+                    allocator.destroy_image(depth_image, depth_image_allocation);
+                }
+            });
+
+            let load_shader = |name: &str| shaders::load(&config.shader_directory, name);
+            let pipeline_reflection = reflect_pipeline(mesh_shader_variant, load_shader)
+                .map_err(RenderCtxError::other("reflect the shaders"))?;
+            let descriptor_set_layout = pipeline_reflection
+                .create_descriptor_set_layouts(&device_loader)
+                .map_err(RenderCtxError::other("create the descriptor set layout"))?[0];
+            cleanup.push({
+                let device_loader = device_loader.clone();
+                move || device_loader.destroy_descriptor_set_layout(descriptor_set_layout, None)
+            });
+            let pipeline_layout = pipeline_reflection
+                .create_pipeline_layout(&device_loader, &[descriptor_set_layout])
+                .map_err(RenderCtxError::other("create the pipeline layout"))?;
+            cleanup.push({
+                let device_loader = device_loader.clone();
+                move || device_loader.destroy_pipeline_layout(pipeline_layout, None)
+            });
+
+            let pipeline_cache = PipelineCache::load(
+                device_loader.clone(),
+                &selected_device.properties,
+                &pipeline_cache::default_directory(),
+            )
+            .map_err(RenderCtxError::other("create the pipeline cache"))?;
+
+            let pipeline = create_pipeline(
                 &device_loader,
-                mesh_shader,
-                None,
-                fragment_shader,
+                mesh_shader_variant,
                 pipeline_layout,
+                pipeline_cache.pipeline_cache,
+                max_spirv_version,
+                load_shader,
             )
-            .unwrap();
+            .map_err(RenderCtxError::other("create the pipeline"))?;
+            debug_names.set_object_name(pipeline, pipeline_name(mesh_shader_variant));
+            cleanup.push({
+                let device_loader = device_loader.clone();
+                move || device_loader.destroy_pipeline(pipeline, None)
+            });
 
-            device_loader.destroy_shader_module(fragment_shader, None);
-            device_loader.destroy_shader_module(mesh_shader, None);
+            let output_pass = OutputPass::new(
+                device_loader.clone(),
+                allocator.clone(),
+                debug_names.clone(),
+                swapchain_extent,
+                surface_format.format,
+                output_transform,
+                config.paper_white_nits,
+                pipeline_cache.pipeline_cache,
+                max_spirv_version,
+                load_shader,
+            )
+            .map_err(RenderCtxError::other("create the output pass"))?;
 
-            let frames: Vec<_> = (0..frame::NUM_FRAMES)
-                .into_iter()
-                .map(|_| {
-                    ManuallyDrop::new(Frame::new(
+            let timestamp_period = selected_device.properties.limits.timestamp_period;
+            let timestamp_valid_bits = instance_loader
+                .get_physical_device_queue_family_properties(physical_device)
+                [direct_queue_family_index as usize]
+                .timestamp_valid_bits;
+            let pipeline_statistic_flags =
+                pipeline_statistics::supported_flags(mesh_shader_variant, &optional_features);
+
+            let frames = (0..config.frames_in_flight)
+                .map(|index| {
+                    Frame::new(
+                        index,
+                        &debug_names,
                         device_loader.clone(),
                         allocator.clone(),
+                        direct_queue_family_index,
                         descriptor_set_layout,
-                    ))
+                        &pipeline_reflection.descriptor_pool_sizes(0),
+                        timestamp_period,
+                        timestamp_valid_bits,
+                        pipeline_statistic_flags,
+                    )
                 })
-                .collect();
+                .collect::<Result<Vec<_>, _>>()?;
+
+            //from here on the Drop impl is responsible for everything
+            cleanup.dismiss();
 
-            Self {
+            Ok(Self {
                 entry_loader,
 
                 instance_loader,
                 surface_loader,
+                debug_messenger,
+                debug_names,
 
                 surface,
 
+                physical_device,
                 device_loader,
                 swapchain_loader,
                 mesh_shader_loader,
 
                 allocator: ManuallyDrop::new(allocator),
 
+                direct_queue_family_index,
                 direct_queue,
                 swapchain,
+                swapchain_extent,
+                swapchain_out_of_date: false,
+                surface_format,
+                present_mode,
 
                 depth_image,
                 depth_image_allocation,
@@ -243,13 +589,468 @@ impl RenderCtx {
                 swapchain_images,
                 swapchain_image_views,
 
+                offscreen_target,
+
+                max_spirv_version,
+                pipeline_reflection,
                 descriptor_set_layout,
                 pipeline_layout,
+                pipeline_cache: ManuallyDrop::new(pipeline_cache),
                 pipeline,
+                output_pass: ManuallyDrop::new(output_pass),
+
+                frames: frames.into_iter().map(ManuallyDrop::new).collect(),
+                timestamp_period,
+                timestamp_valid_bits,
+                pipeline_statistic_flags,
+                gpu_profile: GpuProfile::default(),
+                pipeline_statistics_enabled: false,
+                last_pipeline_statistics: None,
+
+                geometry: None,
+            })
+        }
+    }
+
+    //keeps the old swapchain and swapchain_out_of_date while the surface has no area, e.g. while minimized
+    pub fn recreate_swapchain(&mut self, width: u32, height: u32) -> anyhow::Result<()> {
+        if self.offscreen_target.is_some() {
+            anyhow::bail!("Headless render contexts have no swapchain");
+        }
 
-                frames,
+        unsafe {
+            let surface_capabilities = self
+                .surface_loader
+                .get_physical_device_surface_capabilities(self.physical_device, self.surface)?;
+
+            let extent = choose_swapchain_extent(&surface_capabilities, width, height);
+            let image_count = config::choose_image_count(&surface_capabilities);
+
+            if extent.width == 0 || extent.height == 0 {
+                return Ok(());
             }
+
+            self.device_loader.device_wait_idle()?;
+
+            self.destroy_swapchain_resources();
+
+            let old_swapchain = self.swapchain;
+            let result = util::create_swapchain(
+                &self.device_loader,
+                &self.swapchain_loader,
+                self.surface,
+                extent,
+                self.surface_format,
+                self.present_mode,
+                image_count,
+                old_swapchain,
+            );
+            self.swapchain_loader.destroy_swapchain(old_swapchain, None);
+            self.swapchain = vk::SwapchainKHR::null();
+
+            let (swapchain, swapchain_images, swapchain_image_views) = result?;
+            self.swapchain = swapchain;
+            self.swapchain_images = swapchain_images;
+            self.swapchain_image_views = swapchain_image_views;
+            self.swapchain_extent = extent;
+
+            let (depth_image, depth_image_allocation, depth_image_view) = util::create_depth_image(
+                &self.device_loader,
+                &self.allocator,
+                extent.width,
+                extent.height,
+                DEPTH_FORMAT,
+            )?;
+            self.depth_image = depth_image;
+            self.depth_image_allocation = depth_image_allocation;
+            self.depth_image_view = depth_image_view;
+
+            name_swapchain_resources(&self.debug_names, &self.swapchain_images, depth_image);
+
+            self.output_pass.resize(extent)?;
+            self.swapchain_out_of_date = false;
         }
+
+        Ok(())
+    }
+
+    //destroys image views and the depth image, the swapchain itself is kept to be passed as old_swapchain
+    unsafe fn destroy_swapchain_resources(&mut self) {
+        self.swapchain_image_views
+            .drain(..)
+            .for_each(|image_view| self.device_loader.destroy_image_view(image_view, None));
+        self.swapchain_images.clear();
+
+        //already destroyed if a previous recreation failed halfway
+        if self.depth_image != vk::Image::null() {
+            self.device_loader
+                .destroy_image_view(self.depth_image_view, None);
+            self.allocator
+                .destroy_image(self.depth_image, self.depth_image_allocation);
+            self.depth_image_view = vk::ImageView::null();
+            self.depth_image = vk::Image::null();
+        }
+    }
+
+    //builds the mesh and output pipelines from newly loaded spir-v, the current ones are kept if that fails
+    pub fn reload_pipeline(
+        &mut self,
+        load_shader: impl Fn(&str) -> anyhow::Result<Cow<'static, [u32]>>,
+    ) -> anyhow::Result<()> {
+        unsafe {
+            let mesh_shader_variant = self
+                .mesh_shader_loader
+                .as_ref()
+                .map(|mesh_shader_loader| mesh_shader_loader.variant());
+
+            //loaded once, so the code that is reflected is the code the pipeline is created from
+            let codes = pipeline_shader_names(mesh_shader_variant)
+                .into_iter()
+                .chain([output::VERTEX_SHADER_PATH, output::FRAGMENT_SHADER_PATH])
+                .map(|name| Ok((name, load_shader(name)?)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let loaded_shader = |name: &str| {
+                codes
+                    .iter()
+                    .find(|(loaded_name, _)| *loaded_name == name)
+                    .map(|(_, code)| code.clone())
+                    .ok_or_else(|| anyhow::anyhow!("{} was not loaded", name))
+            };
+
+            //the layout, descriptor sets and geometry are kept, so the new shaders have to fit them
+            let pipeline_reflection = reflect_pipeline(mesh_shader_variant, loaded_shader)?;
+            self.pipeline_reflection
+                .ensure_layout_compatible(&pipeline_reflection)
+                .context(
+                    "The reloaded shaders do not fit the pipeline layout, restart to apply them",
+                )?;
+
+            let pipeline = create_pipeline(
+                &self.device_loader,
+                mesh_shader_variant,
+                self.pipeline_layout,
+                self.pipeline_cache.pipeline_cache,
+                self.max_spirv_version,
+                loaded_shader,
+            )?;
+            self.debug_names
+                .set_object_name(pipeline, pipeline_name(mesh_shader_variant));
+
+            let output_pipeline = match self.output_pass.create_reloaded_pipeline(
+                self.pipeline_cache.pipeline_cache,
+                self.max_spirv_version,
+                loaded_shader,
+            ) {
+                Ok(output_pipeline) => output_pipeline,
+                Err(e) => {
+                    self.device_loader.destroy_pipeline(pipeline, None);
+                    return Err(e);
+                }
+            };
+
+            //the old pipelines may still be used by frames in flight
+            if let Err(result) = self.device_loader.device_wait_idle() {
+                self.device_loader.destroy_pipeline(pipeline, None);
+                self.device_loader.destroy_pipeline(output_pipeline, None);
+                return Err(result.into());
+            }
+
+            self.device_loader.destroy_pipeline(self.pipeline, None);
+            self.pipeline = pipeline;
+            self.output_pass.replace_pipeline(output_pipeline);
+        }
+
+        Ok(())
+    }
+
+    //waits for the gpu, frames beyond count are destroyed and missing ones are created with the current geometry
+    pub fn set_frames_in_flight(&mut self, count: usize) -> anyhow::Result<()> {
+        check_frames_in_flight(count)?;
+
+        unsafe {
+            self.device_loader.device_wait_idle()?;
+
+            while self.frames.len() > count {
+                let mut frame = self.frames.pop().unwrap();
+                ManuallyDrop::drop(&mut frame);
+            }
+
+            while self.frames.len() < count {
+                let frame = Frame::new(
+                    self.frames.len(),
+                    &self.debug_names,
+                    self.device_loader.clone(),
+                    Arc::clone(&self.allocator),
+                    self.direct_queue_family_index,
+                    self.descriptor_set_layout,
+                    &self.pipeline_reflection.descriptor_pool_sizes(0),
+                    self.timestamp_period,
+                    self.timestamp_valid_bits,
+                    self.pipeline_statistic_flags,
+                )?;
+                if let Some(geometry) = &self.geometry {
+                    frame.write_geometry_descriptors(geometry, &self.pipeline_reflection);
+                }
+                self.frames.push(ManuallyDrop::new(frame));
+            }
+        }
+
+        Ok(())
+    }
+
+    //counters of the most recently completed frame, None if disabled or unsupported
+    pub fn pipeline_statistics(&self) -> Option<&PipelineStatistics> {
+        self.last_pipeline_statistics.as_ref()
+    }
+
+    //a scene with a single node drawing mesh with the default material
+    pub fn set_mesh(&mut self, mesh: &Mesh) -> anyhow::Result<()> {
+        self.set_scene(&Scene::from_mesh(mesh.clone()))
+    }
+
+    //the instances follow the world transforms of the last Scene::update_world_transforms
+    pub fn set_scene(&mut self, scene: &Scene) -> anyhow::Result<()> {
+        unsafe {
+            //the descriptor sets of all frames are rewritten, so nothing may be in flight
+            self.device_loader.device_wait_idle()?;
+
+            let geometry = Geometry::new(
+                &self.device_loader,
+                &self.allocator,
+                self.direct_queue_family_index,
+                self.direct_queue,
+                scene,
+            )?;
+            self.frames.iter().for_each(|frame| {
+                frame.write_geometry_descriptors(&geometry, &self.pipeline_reflection)
+            });
+
+            self.geometry = Some(geometry);
+        }
+
+        Ok(())
+    }
+}
+
+fn check_frames_in_flight(count: usize) -> Result<(), RenderCtxError> {
+    if count == 0 {
+        return Err(RenderCtxError::InvalidConfig(
+            "at least one frame must be in flight",
+        ));
+    }
+
+    Ok(())
+}
+
+//checked up front, instance creation would only report which kind of thing is missing
+unsafe fn check_instance_support(
+    entry_loader: &Entry,
+    layers: &[&CStr],
+    extensions: &[&CStr],
+) -> Result<(), RenderCtxError> {
+    for layer in layers {
+        if !is_instance_layer_available(entry_loader, layer)? {
+            return Err(RenderCtxError::MissingInstanceLayer(
+                layer.to_string_lossy().into_owned(),
+            ));
+        }
+    }
+
+    for extension in extensions {
+        if !is_instance_extension_available(entry_loader, extension)? {
+            return Err(RenderCtxError::MissingInstanceExtension(
+                extension.to_string_lossy().into_owned(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+unsafe fn is_instance_layer_available(
+    entry_loader: &Entry,
+    layer: &CStr,
+) -> Result<bool, RenderCtxError> {
+    Ok(entry_loader
+        .enumerate_instance_layer_properties()
+        .map_err(RenderCtxError::vulkan("enumerate the instance layers"))?
+        .iter()
+        .any(|properties| CStr::from_ptr(properties.layer_name.as_ptr()) == layer))
+}
+
+unsafe fn is_instance_extension_available(
+    entry_loader: &Entry,
+    extension: &CStr,
+) -> Result<bool, RenderCtxError> {
+    Ok(entry_loader
+        .enumerate_instance_extension_properties(None)
+        .map_err(RenderCtxError::vulkan("enumerate the instance extensions"))?
+        .iter()
+        .any(|properties| CStr::from_ptr(properties.extension_name.as_ptr()) == extension))
+}
+
+unsafe fn name_swapchain_resources(
+    debug_names: &DebugNames,
+    swapchain_images: &[vk::Image],
+    depth_image: vk::Image,
+) {
+    for (index, image) in swapchain_images.iter().enumerate() {
+        debug_names.set_object_name(*image, &format!("swapchain image {}", index));
+    }
+    debug_names.set_object_name(depth_image, "depth image");
+}
+
+fn pipeline_name(mesh_shader_variant: Option<MeshShaderVariant>) -> &'static str {
+    match mesh_shader_variant {
+        Some(_) => "mesh pipeline",
+        None => "vertex pipeline",
+    }
+}
+
+//the order is fragment, then vertex or mesh, then task
+fn pipeline_shader_names(mesh_shader_variant: Option<MeshShaderVariant>) -> Vec<&'static str> {
+    match mesh_shader_variant {
+        Some(mesh_shader_variant) => vec![
+            FRAGMENT_SHADER_PATH,
+            mesh_shader_variant.mesh_shader_path(),
+            mesh_shader_variant.task_shader_path(),
+        ],
+        None => vec![FRAGMENT_SHADER_PATH, VERTEX_SHADER_PATH],
+    }
+}
+
+//the shaders also have to agree with the workgroup and meshlet sizes the geometry is built for
+fn reflect_pipeline(
+    mesh_shader_variant: Option<MeshShaderVariant>,
+    load_shader: impl Fn(&str) -> anyhow::Result<Cow<'static, [u32]>>,
+) -> anyhow::Result<PipelineReflection> {
+    let mut stages = Vec::new();
+
+    for name in pipeline_shader_names(mesh_shader_variant) {
+        let stage = ShaderReflection::parse(&load_shader(name)?)
+            .with_context(|| format!("Failed to reflect {}", name))?;
+
+        if stage
+            .stage
+            .intersects(vk::ShaderStageFlags::TASK_EXT | vk::ShaderStageFlags::TASK_NV)
+        {
+            ensure!(
+                stage.local_size == Some([geometry::TASK_WORKGROUP_SIZE, 1, 1]),
+                "{} has a workgroup size of {:?} instead of {}",
+                name,
+                stage.local_size,
+                geometry::TASK_WORKGROUP_SIZE
+            );
+        }
+        if stage
+            .stage
+            .intersects(vk::ShaderStageFlags::MESH_EXT | vk::ShaderStageFlags::MESH_NV)
+        {
+            ensure!(
+                stage.max_output_vertices >= Some(meshlet::MAX_VERTICES as u32)
+                    && stage.max_output_primitives >= Some(meshlet::MAX_PRIMITIVES as u32),
+                "{} outputs at most {:?} vertices and {:?} primitives, meshlets have up to {} and {}",
+                name,
+                stage.max_output_vertices,
+                stage.max_output_primitives,
+                meshlet::MAX_VERTICES,
+                meshlet::MAX_PRIMITIVES
+            );
+        }
+
+        stages.push(stage);
+    }
+
+    let pipeline_reflection = reflection::merge(&stages)?;
+    ensure!(
+        pipeline_reflection.sets.len() == 1 && pipeline_reflection.sets.contains_key(&0),
+        "The shaders must use exactly descriptor set 0, not {:?}",
+        pipeline_reflection.sets.keys().collect::<Vec<_>>()
+    );
+    //every draw pushes its transform, material factors and meshlet range
+    ensure!(
+        matches!(
+            pipeline_reflection.push_constant_ranges[..],
+            [vk::PushConstantRange {
+                offset: 0,
+                size: geometry::DRAW_PUSH_CONSTANTS_SIZE,
+                ..
+            }]
+        ),
+        "The shaders must use a single push constant block of {} bytes, not {:?}",
+        geometry::DRAW_PUSH_CONSTANTS_SIZE,
+        pipeline_reflection.push_constant_ranges
+    );
+
+    Ok(pipeline_reflection)
+}
+
+//spir-v is loaded by file name through load_shader, the shader modules are destroyed again before returning
+unsafe fn create_pipeline(
+    device: &Device,
+    mesh_shader_variant: Option<MeshShaderVariant>,
+    pipeline_layout: vk::PipelineLayout,
+    pipeline_cache: vk::PipelineCache,
+    max_spirv_version: SpirvVersion,
+    load_shader: impl Fn(&str) -> anyhow::Result<Cow<'static, [u32]>>,
+) -> anyhow::Result<vk::Pipeline> {
+    let names = pipeline_shader_names(mesh_shader_variant);
+
+    let mut shader_modules = Vec::with_capacity(names.len());
+    let result = (|| {
+        for name in names {
+            let code = load_shader(name)?;
+            spirv::validate(&code, max_spirv_version)
+                .with_context(|| format!("Invalid shader {}", name))?;
+            shader_modules.push(util::create_shader_module_from_spirv(device, &code)?);
+        }
+
+        match mesh_shader_variant {
+            Some(mesh_shader_variant) => util::create_mesh_pipeline(
+                device,
+                pipeline_cache,
+                mesh_shader_variant,
+                shader_modules[1],
+                Some(shader_modules[2]),
+                shader_modules[0],
+                pipeline_layout,
+            ),
+            None => util::create_vertex_pipeline(
+                device,
+                pipeline_cache,
+                shader_modules[1],
+                shader_modules[0],
+                pipeline_layout,
+            ),
+        }
+    })();
+
+    shader_modules
+        .into_iter()
+        .for_each(|shader_module| device.destroy_shader_module(shader_module, None));
+
+    result
+}
+
+//width and height are only used if the surface does not dictate its extent, e.g. on wayland
+fn choose_swapchain_extent(
+    surface_capabilities: &vk::SurfaceCapabilitiesKHR,
+    width: u32,
+    height: u32,
+) -> vk::Extent2D {
+    if surface_capabilities.current_extent.width != u32::MAX {
+        return surface_capabilities.current_extent;
+    }
+
+    vk::Extent2D {
+        width: width.clamp(
+            surface_capabilities.min_image_extent.width,
+            surface_capabilities.max_image_extent.width,
+        ),
+        height: height.clamp(
+            surface_capabilities.min_image_extent.height,
+            surface_capabilities.max_image_extent.height,
+        ),
     }
 }
 
@@ -262,30 +1063,34 @@ impl Drop for RenderCtx {
                 .iter_mut()
                 .for_each(|frame| ManuallyDrop::drop(frame));
 
+            self.geometry = None;
+            self.offscreen_target = None;
+            ManuallyDrop::drop(&mut self.output_pass);
+
             self.device_loader.destroy_pipeline(self.pipeline, None);
+            ManuallyDrop::drop(&mut self.pipeline_cache);
             self.device_loader
                 .destroy_pipeline_layout(self.pipeline_layout, None);
             self.device_loader
                 .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
 
-            self.swapchain_image_views
-                .iter()
-                .for_each(|image_view| self.device_loader.destroy_image_view(*image_view, None));
+            self.destroy_swapchain_resources();
 
-            self.device_loader
-                .destroy_image_view(self.depth_image_view, None);
-            self.allocator
-                .destroy_image(self.depth_image, self.depth_image_allocation);
-
-            self.swapchain_loader
-                .destroy_swapchain(self.swapchain, None);
+            //the swapchain and surface functions are not loaded for headless contexts
+            if self.swapchain != vk::SwapchainKHR::null() {
+                self.swapchain_loader
+                    .destroy_swapchain(self.swapchain, None);
+            }
 
             ManuallyDrop::drop(&mut self.allocator);
 
             self.device_loader.destroy_device(None);
 
-            self.surface_loader.destroy_surface(self.surface, None);
+            if self.surface != vk::SurfaceKHR::null() {
+                self.surface_loader.destroy_surface(self.surface, None);
+            }
 
+            self.debug_messenger = None;
             self.instance_loader.destroy_instance(None);
         }
     }
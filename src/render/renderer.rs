@@ -1,15 +1,54 @@
-use std::slice;
+use std::{mem, slice};
 
+use anyhow::{Context, Result};
 use ash::vk;
 
-use crate::render::render_ctx::{HEIGHT, WIDTH};
 use crate::render::Camera;
-use crate::{
-    render::{frame::Frame, render_ctx},
-    RenderCtx,
+use crate::render::{
+    culling::Frustum,
+    frame::{Frame, FrameUniform},
+    geometry::{DrawPushConstants, DRAW_PUSH_CONSTANTS_SIZE},
+    render_ctx::RenderCtx,
 };
 
-pub unsafe fn render_frame(ctx: &RenderCtx, frame_index: &mut usize, camera: &Camera) {
+//an out of date swapchain is only flagged, it has to be recreated before the next frame
+pub unsafe fn render_frame(
+    ctx: &mut RenderCtx,
+    frame_index: &mut usize,
+    camera: &Camera,
+) -> Result<()> {
+    //the timings of the previous submission of this frame are read before they are reset during recording,
+    //they are taken, so they are not added again if acquiring fails and the frame is not submitted
+    let current_frame = &ctx.frames[*frame_index];
+    ctx.device_loader
+        .wait_for_fences(slice::from_ref(&current_frame.fence), true, u64::MAX)?;
+    ctx.gpu_profile
+        .add_frame(current_frame.profiler.take_results());
+    if let Some(pipeline_statistics) = current_frame
+        .pipeline_statistics_query
+        .as_ref()
+        .and_then(|query| query.take_results())
+    {
+        ctx.last_pipeline_statistics = Some(pipeline_statistics);
+    }
+
+    if draw_and_present(ctx, frame_index, camera)? {
+        ctx.swapchain_out_of_date = true;
+    }
+
+    if let Some(debug_messenger) = &ctx.debug_messenger {
+        debug_messenger.check();
+    }
+
+    Ok(())
+}
+
+//returns true if the swapchain is out of date or suboptimal and has to be recreated
+unsafe fn draw_and_present(
+    ctx: &RenderCtx,
+    frame_index: &mut usize,
+    camera: &Camera,
+) -> Result<bool> {
     let device_loader = &ctx.device_loader;
     let direct_queue = ctx.direct_queue;
     let swapchain_loader = &ctx.swapchain_loader;
@@ -21,44 +60,42 @@ pub unsafe fn render_frame(ctx: &RenderCtx, frame_index: &mut usize, camera: &Ca
     let render_semaphore = current_frame.render_semaphore;
 
     let fence = current_frame.fence;
-    device_loader
-        .wait_for_fences(slice::from_ref(&fence), true, u64::MAX)
-        .unwrap();
-    device_loader.reset_fences(slice::from_ref(&fence)).unwrap();
+    device_loader.wait_for_fences(slice::from_ref(&fence), true, u64::MAX)?;
+
+    //the fence is only reset once it is certain that work will be submitted
+    let (image_index, acquire_suboptimal) = match swapchain_loader.acquire_next_image(
+        swapchain,
+        u64::MAX,
+        present_semaphore,
+        vk::Fence::null(),
+    ) {
+        Ok(result) => result,
+        Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => return Ok(true),
+        Err(result) => return Err(result).context("Failed to acquire a swapchain image"),
+    };
+
+    device_loader.reset_fences(slice::from_ref(&fence))?;
 
-    let command_pool = current_frame.command_pool;
     let command_buffer = current_frame.command_buffer;
-
-    device_loader
-        .reset_command_buffer(
-            command_buffer,
-            vk::CommandBufferResetFlags::RELEASE_RESOURCES,
-        )
-        .unwrap();
-    device_loader
-        .reset_command_pool(command_pool, vk::CommandPoolResetFlags::RELEASE_RESOURCES)
-        .unwrap();
-
-    let image_index = swapchain_loader
-        .acquire_next_image(swapchain, u64::MAX, present_semaphore, vk::Fence::null())
-        .unwrap()
-        .0;
-
-    let command_buffer_begin_info =
-        vk::CommandBufferBeginInfo::default().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
-
-    device_loader
-        .begin_command_buffer(command_buffer, &command_buffer_begin_info)
-        .unwrap();
-
     let image = ctx.swapchain_images[image_index as usize];
 
+    begin_command_buffer(ctx, current_frame)?;
+    ctx.debug_names.begin_label(command_buffer, "frame");
+    current_frame.profiler.begin_scope(command_buffer, "frame");
+    record_rendering(
+        ctx,
+        current_frame,
+        image,
+        ctx.swapchain_image_views[image_index as usize],
+        camera,
+    );
+
     let barrier = vk::ImageMemoryBarrier2::default()
-        .src_stage_mask(vk::PipelineStageFlags2::TOP_OF_PIPE)
-        .dst_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
-        .dst_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
-        .old_layout(vk::ImageLayout::UNDEFINED)
-        .new_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .src_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+        .src_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags2::BOTTOM_OF_PIPE)
+        .old_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
         .image(image)
         .subresource_range(
             vk::ImageSubresourceRange::default()
@@ -72,8 +109,102 @@ pub unsafe fn render_frame(ctx: &RenderCtx, frame_index: &mut usize, camera: &Ca
         &vk::DependencyInfo::default().image_memory_barriers(slice::from_ref(&barrier)),
     );
 
+    current_frame.profiler.end_scope(command_buffer);
+    ctx.debug_names.end_label(command_buffer);
+
+    device_loader.end_command_buffer(command_buffer)?;
+
+    let wait_semaphores = [present_semaphore];
+    let wait_dst_stage_mask = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+
+    let submit_info = vk::SubmitInfo::default()
+        .wait_semaphores(&wait_semaphores)
+        .wait_dst_stage_mask(&wait_dst_stage_mask)
+        .command_buffers(slice::from_ref(&command_buffer))
+        .signal_semaphores(slice::from_ref(&render_semaphore));
+
+    device_loader.queue_submit(direct_queue, slice::from_ref(&submit_info), fence)?;
+
+    let present_info = vk::PresentInfoKHR::default()
+        .wait_semaphores(slice::from_ref(&render_semaphore))
+        .swapchains(slice::from_ref(&swapchain))
+        .image_indices(slice::from_ref(&image_index));
+
+    match swapchain_loader.queue_present(direct_queue, &present_info) {
+        Ok(present_suboptimal) => Ok(acquire_suboptimal || present_suboptimal),
+        Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => Ok(true),
+        Err(result) => Err(result).context("Failed to present a swapchain image"),
+    }
+}
+
+unsafe fn begin_command_buffer(ctx: &RenderCtx, current_frame: &Frame) -> Result<(), vk::Result> {
+    let device_loader = &ctx.device_loader;
+    let command_pool = current_frame.command_pool;
+    let command_buffer = current_frame.command_buffer;
+
+    device_loader.reset_command_buffer(
+        command_buffer,
+        vk::CommandBufferResetFlags::RELEASE_RESOURCES,
+    )?;
+    device_loader.reset_command_pool(command_pool, vk::CommandPoolResetFlags::RELEASE_RESOURCES)?;
+
+    let command_buffer_begin_info =
+        vk::CommandBufferBeginInfo::default().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+    device_loader.begin_command_buffer(command_buffer, &command_buffer_begin_info)?;
+
+    current_frame.profiler.reset(command_buffer);
+    if let Some(pipeline_statistics_query) = &current_frame.pipeline_statistics_query {
+        pipeline_statistics_query.reset(command_buffer);
+    }
+
+    Ok(())
+}
+
+//the mesh pass renders into the scene color, the output pass writes it to image, which is left in COLOR_ATTACHMENT_OPTIMAL
+unsafe fn record_rendering(
+    ctx: &RenderCtx,
+    current_frame: &Frame,
+    image: vk::Image,
+    image_view: vk::ImageView,
+    camera: &Camera,
+) {
+    let device_loader = &ctx.device_loader;
+    let command_buffer = current_frame.command_buffer;
+    let output_pass = &ctx.output_pass;
+
+    let color_subresource_range = vk::ImageSubresourceRange::default()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .layer_count(1)
+        .level_count(1);
+
+    //the scene color is shared by all frames, so the output pass of the previous one has to be done reading it
+    let barriers = [
+        vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::FRAGMENT_SHADER)
+            .dst_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+            .dst_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .image(output_pass.scene_color_image)
+            .subresource_range(color_subresource_range),
+        vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::TOP_OF_PIPE)
+            .dst_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+            .dst_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .image(image)
+            .subresource_range(color_subresource_range),
+    ];
+
+    device_loader.cmd_pipeline_barrier2(
+        command_buffer,
+        &vk::DependencyInfo::default().image_memory_barriers(&barriers),
+    );
+
     let color_attachment = vk::RenderingAttachmentInfo::default()
-        .image_view(ctx.swapchain_image_views[image_index as usize])
+        .image_view(output_pass.scene_color_image_view)
         .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
         .load_op(vk::AttachmentLoadOp::CLEAR)
         .store_op(vk::AttachmentStoreOp::STORE)
@@ -96,26 +227,97 @@ pub unsafe fn render_frame(ctx: &RenderCtx, frame_index: &mut usize, camera: &Ca
         });
 
     let rendering_info = vk::RenderingInfo::default()
-        .render_area(
-            vk::Rect2D::default().extent(vk::Extent2D::default().width(WIDTH).height(HEIGHT)),
-        )
+        .render_area(vk::Rect2D::default().extent(ctx.swapchain_extent))
         .layer_count(1)
         .color_attachments(slice::from_ref(&color_attachment))
         .depth_attachment(&depth_attachment);
 
     device_loader.cmd_begin_rendering(command_buffer, &rendering_info);
 
-    render_frame_inner(ctx, current_frame, image_index as usize, camera);
+    ctx.debug_names.begin_label(command_buffer, "mesh pass");
+    current_frame
+        .profiler
+        .begin_scope(command_buffer, "mesh pass");
+    render_frame_inner(ctx, current_frame, camera);
+    current_frame.profiler.end_scope(command_buffer);
+    ctx.debug_names.end_label(command_buffer);
 
     device_loader.cmd_end_rendering(command_buffer);
 
     let barrier = vk::ImageMemoryBarrier2::default()
         .src_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
         .src_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
-        .dst_stage_mask(vk::PipelineStageFlags2::BOTTOM_OF_PIPE)
+        .dst_stage_mask(vk::PipelineStageFlags2::FRAGMENT_SHADER)
+        .dst_access_mask(vk::AccessFlags2::SHADER_SAMPLED_READ)
         .old_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
-        .new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
-        .image(image)
+        .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .image(output_pass.scene_color_image)
+        .subresource_range(color_subresource_range);
+
+    device_loader.cmd_pipeline_barrier2(
+        command_buffer,
+        &vk::DependencyInfo::default().image_memory_barriers(slice::from_ref(&barrier)),
+    );
+
+    //every pixel is written, so the previous contents are not loaded
+    let output_attachment = vk::RenderingAttachmentInfo::default()
+        .image_view(image_view)
+        .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .store_op(vk::AttachmentStoreOp::STORE);
+
+    let rendering_info = vk::RenderingInfo::default()
+        .render_area(vk::Rect2D::default().extent(ctx.swapchain_extent))
+        .layer_count(1)
+        .color_attachments(slice::from_ref(&output_attachment));
+
+    device_loader.cmd_begin_rendering(command_buffer, &rendering_info);
+
+    ctx.debug_names.begin_label(command_buffer, "output pass");
+    current_frame
+        .profiler
+        .begin_scope(command_buffer, "output pass");
+    output_pass.draw(command_buffer, ctx.swapchain_extent);
+    current_frame.profiler.end_scope(command_buffer);
+    ctx.debug_names.end_label(command_buffer);
+
+    device_loader.cmd_end_rendering(command_buffer);
+}
+
+//renders a single frame into the offscreen target of a headless context and returns it as tightly packed rgba8
+pub unsafe fn render_offscreen(ctx: &RenderCtx, camera: &Camera) -> Result<Vec<u8>> {
+    let offscreen_target = ctx
+        .offscreen_target
+        .as_ref()
+        .context("Render context is not headless")?;
+
+    let device_loader = &ctx.device_loader;
+    let current_frame = &ctx.frames[0];
+    let command_buffer = current_frame.command_buffer;
+    let fence = current_frame.fence;
+
+    device_loader.wait_for_fences(slice::from_ref(&fence), true, u64::MAX)?;
+    device_loader.reset_fences(slice::from_ref(&fence))?;
+
+    begin_command_buffer(ctx, current_frame)?;
+    ctx.debug_names
+        .begin_label(command_buffer, "offscreen frame");
+    record_rendering(
+        ctx,
+        current_frame,
+        offscreen_target.image,
+        offscreen_target.image_view,
+        camera,
+    );
+
+    let barrier = vk::ImageMemoryBarrier2::default()
+        .src_stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)
+        .src_access_mask(vk::AccessFlags2::COLOR_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags2::COPY)
+        .dst_access_mask(vk::AccessFlags2::TRANSFER_READ)
+        .old_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+        .image(offscreen_target.image)
         .subresource_range(
             vk::ImageSubresourceRange::default()
                 .aspect_mask(vk::ImageAspectFlags::COLOR)
@@ -128,38 +330,63 @@ pub unsafe fn render_frame(ctx: &RenderCtx, frame_index: &mut usize, camera: &Ca
         &vk::DependencyInfo::default().image_memory_barriers(slice::from_ref(&barrier)),
     );
 
-    device_loader.end_command_buffer(command_buffer).unwrap();
+    let extent = offscreen_target.extent;
+    let region = vk::BufferImageCopy::default()
+        .image_subresource(
+            vk::ImageSubresourceLayers::default()
+                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                .layer_count(1),
+        )
+        .image_extent(
+            vk::Extent3D::default()
+                .width(extent.width)
+                .height(extent.height)
+                .depth(1),
+        );
 
-    let wait_semaphores = [present_semaphore];
-    let wait_dst_stage_mask = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+    device_loader.cmd_copy_image_to_buffer(
+        command_buffer,
+        offscreen_target.image,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        offscreen_target.readback_buffer.buffer,
+        slice::from_ref(&region),
+    );
 
-    let submit_info = vk::SubmitInfo::default()
-        .wait_semaphores(&wait_semaphores)
-        .wait_dst_stage_mask(&wait_dst_stage_mask)
-        .command_buffers(slice::from_ref(&command_buffer))
-        .signal_semaphores(slice::from_ref(&render_semaphore));
+    let barrier = vk::BufferMemoryBarrier2::default()
+        .src_stage_mask(vk::PipelineStageFlags2::COPY)
+        .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+        .dst_access_mask(vk::AccessFlags2::HOST_READ)
+        .buffer(offscreen_target.readback_buffer.buffer)
+        .size(vk::WHOLE_SIZE);
 
-    device_loader
-        .queue_submit(direct_queue, slice::from_ref(&submit_info), fence)
-        .unwrap();
+    device_loader.cmd_pipeline_barrier2(
+        command_buffer,
+        &vk::DependencyInfo::default().buffer_memory_barriers(slice::from_ref(&barrier)),
+    );
+    ctx.debug_names.end_label(command_buffer);
 
-    let present_info = vk::PresentInfoKHR::default()
-        .wait_semaphores(slice::from_ref(&render_semaphore))
-        .swapchains(slice::from_ref(&swapchain))
-        .image_indices(slice::from_ref(&image_index));
+    device_loader.end_command_buffer(command_buffer)?;
 
-    swapchain_loader
-        .queue_present(direct_queue, &present_info)
-        .unwrap();
+    let submit_info = vk::SubmitInfo::default().command_buffers(slice::from_ref(&command_buffer));
+
+    device_loader.queue_submit(ctx.direct_queue, slice::from_ref(&submit_info), fence)?;
+    device_loader.wait_for_fences(slice::from_ref(&fence), true, u64::MAX)?;
+    offscreen_target.readback_buffer.invalidate()?;
+
+    if let Some(debug_messenger) = &ctx.debug_messenger {
+        debug_messenger.check();
+    }
+
+    Ok(offscreen_target.read_rgba8())
 }
 
-unsafe fn render_frame_inner(
-    ctx: &RenderCtx,
-    current_frame: &Frame,
-    _image_index: usize,
-    camera: &Camera,
-) {
-    *(current_frame.uniform_buffer.memory.unwrap() as *mut _) = camera.view_projection_matrix;
+unsafe fn render_frame_inner(ctx: &RenderCtx, current_frame: &Frame, camera: &Camera) {
+    *(current_frame.uniform_buffer.memory.unwrap() as *mut _) = FrameUniform {
+        view_projection_matrix: camera.view_projection_matrix,
+        frustum_planes: Frustum::from_matrix(&camera.view_projection_matrix).planes,
+        camera_position: camera.position,
+    };
 
     let command_buffer = current_frame.command_buffer;
 
@@ -179,19 +406,81 @@ unsafe fn render_frame_inner(
     );
 
     let viewport = vk::Viewport::default()
-        .width(render_ctx::WIDTH as _)
-        .height(render_ctx::HEIGHT as _)
+        .width(ctx.swapchain_extent.width as _)
+        .height(ctx.swapchain_extent.height as _)
         .max_depth(1.0);
-    let scissor = vk::Rect2D::default().extent(vk::Extent2D {
-        width: render_ctx::WIDTH,
-        height: render_ctx::HEIGHT,
-    });
+    let scissor = vk::Rect2D::default().extent(ctx.swapchain_extent);
 
     ctx.device_loader
         .cmd_set_viewport(command_buffer, 0, slice::from_ref(&viewport));
     ctx.device_loader
         .cmd_set_scissor(command_buffer, 0, slice::from_ref(&scissor));
 
-    ctx.mesh_shader_loader
-        .cmd_draw_mesh_tasks(command_buffer, 1, 0);
+    let pipeline_statistics_query = current_frame
+        .pipeline_statistics_query
+        .as_ref()
+        .filter(|_| ctx.pipeline_statistics_enabled);
+    if let Some(pipeline_statistics_query) = pipeline_statistics_query {
+        pipeline_statistics_query.begin(command_buffer);
+    }
+
+    if let Some(geometry) = &ctx.geometry {
+        if ctx.mesh_shader_loader.is_none() {
+            ctx.device_loader.cmd_bind_vertex_buffers(
+                command_buffer,
+                0,
+                slice::from_ref(&geometry.vertex_buffer.buffer),
+                &[0],
+            );
+            ctx.device_loader.cmd_bind_index_buffer(
+                command_buffer,
+                geometry.index_buffer.buffer,
+                0,
+                vk::IndexType::UINT32,
+            );
+        }
+
+        //checked against the shaders when the pipeline is reflected
+        let push_constant_stages = ctx.pipeline_reflection.push_constant_ranges[0].stage_flags;
+
+        for instance in &geometry.instances {
+            let range = &geometry.primitives[instance.primitive];
+            let push_constants = instance.push_constants(range);
+            ctx.device_loader.cmd_push_constants(
+                command_buffer,
+                ctx.pipeline_layout,
+                push_constant_stages,
+                0,
+                &slice::from_raw_parts(
+                    (&push_constants as *const DrawPushConstants).cast(),
+                    mem::size_of::<DrawPushConstants>(),
+                )[..DRAW_PUSH_CONSTANTS_SIZE as usize],
+            );
+
+            match &ctx.mesh_shader_loader {
+                Some(mesh_shader_loader) => {
+                    mesh_shader_loader.cmd_draw_mesh_tasks(
+                        command_buffer,
+                        range.task_count(),
+                        1,
+                        1,
+                    );
+                }
+                None => {
+                    ctx.device_loader.cmd_draw_indexed(
+                        command_buffer,
+                        range.index_count,
+                        1,
+                        range.first_index,
+                        range.vertex_offset,
+                        0,
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(pipeline_statistics_query) = pipeline_statistics_query {
+        pipeline_statistics_query.end(command_buffer);
+    }
 }
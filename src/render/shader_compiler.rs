@@ -0,0 +1,43 @@
+//shared by build.rs, which includes this file through #[path], and the hot reload path, so both compile shaders alike
+use std::path::Path;
+
+pub fn compile_options() -> Option<shaderc::CompileOptions<'static>> {
+    let mut options = shaderc::CompileOptions::new()?;
+    options.set_target_env(
+        shaderc::TargetEnv::Vulkan,
+        shaderc::EnvVersion::Vulkan1_3 as u32,
+    );
+    Some(options)
+}
+
+//the stage is the second to last extension, e.g. example.ext.task.glsl
+pub fn shader_kind(path: &Path) -> Option<shaderc::ShaderKind> {
+    let stage = path
+        .file_stem()
+        .map(Path::new)
+        .and_then(Path::extension)
+        .and_then(|stage| stage.to_str());
+
+    match stage {
+        Some("vert") => Some(shaderc::ShaderKind::Vertex),
+        Some("frag") => Some(shaderc::ShaderKind::Fragment),
+        Some("task") => Some(shaderc::ShaderKind::Task),
+        Some("mesh") => Some(shaderc::ShaderKind::Mesh),
+        _ => None,
+    }
+}
+
+//diagnostics are formatted as file:line: error: message, warnings are left to the caller
+pub fn compile(
+    compiler: &shaderc::Compiler,
+    options: &shaderc::CompileOptions,
+    path: &Path,
+    source: &str,
+) -> Result<shaderc::CompilationArtifact, String> {
+    let kind =
+        shader_kind(path).ok_or_else(|| format!("{}: unknown shader stage", path.display()))?;
+
+    compiler
+        .compile_into_spirv(source, kind, &path.to_string_lossy(), "main", Some(options))
+        .map_err(|e| e.to_string().trim_end().to_owned())
+}
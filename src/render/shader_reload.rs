@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::render::shader_compiler;
+
+pub const SHADER_EXTENSION: &str = "glsl";
+//checking the modification times of a handful of files is cheap, but not worth doing every frame
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+//polls a directory of glsl shaders for changes and compiles them to spir-v in process
+pub struct ShaderWatcher {
+    directory: PathBuf,
+    modification_times: HashMap<PathBuf, SystemTime>,
+    last_poll: Instant,
+
+    compiler: shaderc::Compiler,
+    options: shaderc::CompileOptions<'static>,
+}
+
+impl ShaderWatcher {
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self> {
+        let compiler = shaderc::Compiler::new().context("Failed to create the shader compiler")?;
+        let options =
+            shader_compiler::compile_options().context("Failed to create compile options")?;
+
+        let mut watcher = Self {
+            directory: directory.into(),
+            modification_times: HashMap::new(),
+            last_poll: Instant::now(),
+
+            compiler,
+            options,
+        };
+        watcher.scan()?;
+
+        Ok(watcher)
+    }
+
+    //returns true if a shader was added or modified since the last call that returned true
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        match self.scan() {
+            Ok(changed) => changed,
+            Err(e) => {
+                log::warn!("Failed to scan {}: {:#}", self.directory.display(), e);
+                false
+            }
+        }
+    }
+
+    fn scan(&mut self) -> Result<bool> {
+        let mut changed = false;
+
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != SHADER_EXTENSION)
+            {
+                continue;
+            }
+
+            let modification_time = fs::metadata(&path)?.modified()?;
+            if self.modification_times.insert(path, modification_time) != Some(modification_time) {
+                changed = true;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    //name is the file name the spir-v is loaded from otherwise, e.g. example.mesh.spv for example.mesh.glsl
+    pub fn compile(&self, name: &str) -> Result<Vec<u32>> {
+        let path = self
+            .directory
+            .join(Path::new(name).with_extension(SHADER_EXTENSION));
+
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        //the error message already names the file and line of each diagnostic
+        let artifact = shader_compiler::compile(&self.compiler, &self.options, &path, &source)
+            .map_err(|e| anyhow!(e))?;
+
+        if artifact.get_num_warnings() > 0 {
+            log::warn!("{}", artifact.get_warning_messages().trim_end());
+        }
+
+        Ok(artifact.as_binary().to_vec())
+    }
+}
@@ -0,0 +1,26 @@
+use std::{borrow::Cow, path::Path};
+
+use anyhow::Result;
+
+#[cfg(feature = "shaders-from-disk")]
+use crate::render::spirv;
+
+//generated by build.rs from shaders/*.glsl, pairs of the spir-v file name and its code
+#[cfg(not(feature = "shaders-from-disk"))]
+include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+
+//name is the spir-v file name, e.g. example.mesh.spv, directory is only used by the shaders-from-disk variant
+#[cfg(not(feature = "shaders-from-disk"))]
+pub fn load(_directory: &Path, name: &str) -> Result<Cow<'static, [u32]>> {
+    SHADERS
+        .iter()
+        .find(|(shader_name, _)| *shader_name == name)
+        .map(|(_, code)| Cow::Borrowed(*code))
+        .ok_or_else(|| anyhow::anyhow!("No embedded shader named {}", name))
+}
+
+//loads from directory instead, so shaders/compile-shaders.sh can update them without rebuilding
+#[cfg(feature = "shaders-from-disk")]
+pub fn load(directory: &Path, name: &str) -> Result<Cow<'static, [u32]>> {
+    Ok(Cow::Owned(spirv::read(directory.join(name))?))
+}
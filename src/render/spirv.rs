@@ -0,0 +1,119 @@
+use std::{fmt, fs, path::Path};
+
+use anyhow::{bail, ensure, Context, Result};
+use ash::vk;
+
+pub const MAGIC: u32 = 0x0723_0203;
+pub const HEADER_WORDS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SpirvVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl SpirvVersion {
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    //the version word is 0x00MMmm00, the other bytes are reserved
+    pub fn from_word(word: u32) -> Result<Self> {
+        ensure!(
+            word & 0xff00_00ff == 0,
+            "Invalid SPIR-V version word {:#010x}",
+            word
+        );
+        Ok(Self::new((word >> 16) as u8, (word >> 8) as u8))
+    }
+
+    //the newest version a device of the given vulkan version must accept
+    pub fn max_supported(api_version: u32) -> Self {
+        match (
+            vk::api_version_major(api_version),
+            vk::api_version_minor(api_version),
+        ) {
+            (1, 0) => Self::new(1, 0),
+            (1, 1) => Self::new(1, 3),
+            (1, 2) => Self::new(1, 5),
+            _ => Self::new(1, 6),
+        }
+    }
+}
+
+impl fmt::Display for SpirvVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+//copies into aligned words, big endian modules are byte swapped to the native order
+pub fn from_bytes(bytes: &[u8]) -> Result<Vec<u32>> {
+    ensure!(
+        bytes.chunks_exact(4).remainder().is_empty(),
+        "SPIR-V must be a whole number of 4 byte words, got {} bytes",
+        bytes.len()
+    );
+    ensure!(
+        bytes.len() >= HEADER_WORDS * 4,
+        "SPIR-V must start with a {} byte header, got {} bytes",
+        HEADER_WORDS * 4,
+        bytes.len()
+    );
+
+    let magic = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    let from_bytes = if u32::from_le_bytes(magic) == MAGIC {
+        u32::from_le_bytes
+    } else if u32::from_be_bytes(magic) == MAGIC {
+        u32::from_be_bytes
+    } else {
+        bail!(
+            "Invalid SPIR-V magic number {:#010x}, expected {:#010x}",
+            u32::from_le_bytes(magic),
+            MAGIC
+        )
+    };
+
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|word| from_bytes(word.try_into().unwrap()))
+        .collect())
+}
+
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<u32>> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    from_bytes(&bytes).with_context(|| format!("Failed to load {}", path.display()))
+}
+
+//checks the header of a module in native word order before it is passed to the driver
+pub fn validate(code: &[u32], max_version: SpirvVersion) -> Result<SpirvVersion> {
+    ensure!(
+        code.len() >= HEADER_WORDS,
+        "SPIR-V must start with a {} word header, got {} words",
+        HEADER_WORDS,
+        code.len()
+    );
+
+    if code[0] != MAGIC {
+        if code[0].swap_bytes() == MAGIC {
+            bail!("SPIR-V is in the wrong byte order");
+        }
+        bail!(
+            "Invalid SPIR-V magic number {:#010x}, expected {:#010x}",
+            code[0],
+            MAGIC
+        );
+    }
+
+    let version = SpirvVersion::from_word(code[1])?;
+    ensure!(
+        version <= max_version,
+        "SPIR-V {} is newer than the {} supported by the device",
+        version,
+        max_version
+    );
+
+    Ok(version)
+}
@@ -1,10 +1,91 @@
-use std::{ffi::CStr, fs::File, io::Read, path::Path, slice};
+use std::{ffi::CStr, mem, ptr, slice, sync::Arc};
 
-use crate::render::render_ctx::{DEPTH_FORMAT, SWAPCHAIN_FORMAT};
+use crate::{
+    asset::Vertex,
+    render::{
+        mesh_shader::MeshShaderVariant, output::SCENE_COLOR_FORMAT, render_ctx::DEPTH_FORMAT,
+        Buffer,
+    },
+};
 use anyhow::Result;
-use ash::{vk, Device};
+use ash::{extensions::khr::Swapchain, vk, Device};
 use vk_mem::{Allocation, AllocationCreateInfo, Allocator, MemoryUsage};
 
+pub fn as_bytes<T: Copy>(data: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(data.as_ptr().cast(), mem::size_of_val(data)) }
+}
+
+pub unsafe fn immediate_submit(
+    device: &Device,
+    queue_family_index: u32,
+    queue: vk::Queue,
+    f: impl FnOnce(vk::CommandBuffer),
+) -> Result<()> {
+    let command_pool_create_info = vk::CommandPoolCreateInfo::default()
+        .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+        .queue_family_index(queue_family_index);
+    let command_pool = device.create_command_pool(&command_pool_create_info, None)?;
+
+    let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::default()
+        .command_pool(command_pool)
+        .level(vk::CommandBufferLevel::PRIMARY)
+        .command_buffer_count(1);
+
+    let result = (|| {
+        let command_buffer = device.allocate_command_buffers(&command_buffer_allocate_info)?[0];
+
+        device.begin_command_buffer(
+            command_buffer,
+            &vk::CommandBufferBeginInfo::default()
+                .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+        )?;
+        f(command_buffer);
+        device.end_command_buffer(command_buffer)?;
+
+        let submit_info =
+            vk::SubmitInfo::default().command_buffers(slice::from_ref(&command_buffer));
+        device.queue_submit(queue, slice::from_ref(&submit_info), vk::Fence::null())?;
+        device.queue_wait_idle(queue)
+    })();
+
+    device.destroy_command_pool(command_pool, None);
+
+    Ok(result?)
+}
+
+pub unsafe fn create_gpu_only_buffer_with_data(
+    device: &Device,
+    allocator: &Arc<Allocator>,
+    queue_family_index: u32,
+    queue: vk::Queue,
+    data: &[u8],
+    usage: vk::BufferUsageFlags,
+) -> Result<Buffer> {
+    //zero sized buffers are not allowed, but empty meshes should still be bindable
+    let size = data.len().max(4) as vk::DeviceSize;
+
+    let staging_buffer =
+        Buffer::new_cpu_to_gpu(allocator.clone(), size, vk::BufferUsageFlags::TRANSFER_SRC)?;
+    ptr::copy_nonoverlapping(data.as_ptr(), staging_buffer.memory.unwrap(), data.len());
+
+    let buffer = Buffer::new_gpu_only(
+        allocator.clone(),
+        size,
+        usage | vk::BufferUsageFlags::TRANSFER_DST,
+    )?;
+
+    immediate_submit(device, queue_family_index, queue, |command_buffer| {
+        device.cmd_copy_buffer(
+            command_buffer,
+            staging_buffer.buffer,
+            buffer.buffer,
+            slice::from_ref(&vk::BufferCopy::default().size(size)),
+        )
+    })?;
+
+    Ok(buffer)
+}
+
 pub fn create_depth_image(
     device: &Device,
     allocator: &Allocator,
@@ -42,35 +123,133 @@ pub fn create_depth_image(
     Ok((image.0, image.1, image_view))
 }
 
-pub fn create_shader_module(device: &Device, path: impl AsRef<Path>) -> Result<vk::ShaderModule> {
-    let mut file = File::open(path)?;
+pub fn create_color_image(
+    device: &Device,
+    allocator: &Allocator,
+    extent: vk::Extent2D,
+    format: vk::Format,
+    usage: vk::ImageUsageFlags,
+) -> Result<(vk::Image, Allocation, vk::ImageView)> {
+    let image_create_info = vk::ImageCreateInfo::default()
+        .image_type(vk::ImageType::TYPE_2D)
+        .format(format)
+        .extent(
+            vk::Extent3D::default()
+                .width(extent.width)
+                .height(extent.height)
+                .depth(1),
+        )
+        .mip_levels(1)
+        .array_layers(1)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .usage(usage)
+        .initial_layout(vk::ImageLayout::UNDEFINED);
 
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
+    let allocation_create_info = AllocationCreateInfo::new().usage(MemoryUsage::GpuOnly);
+    let (image, allocation) =
+        unsafe { allocator.create_image(&image_create_info, &allocation_create_info)? };
 
-    unsafe {
-        let shader_module_create_info = vk::ShaderModuleCreateInfo::default().code(
-            slice::from_raw_parts(buffer.as_ptr().cast(), buffer.len() >> 2),
+    let image_view_create_info = vk::ImageViewCreateInfo::default()
+        .image(image)
+        .view_type(vk::ImageViewType::TYPE_2D)
+        .format(format)
+        .components(Default::default())
+        .subresource_range(
+            vk::ImageSubresourceRange::default()
+                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                .level_count(1)
+                .layer_count(1),
         );
 
-        Ok(device.create_shader_module(&shader_module_create_info, None)?)
+    match unsafe { device.create_image_view(&image_view_create_info, None) } {
+        Ok(image_view) => Ok((image, allocation, image_view)),
+        Err(result) => {
+            unsafe { allocator.destroy_image(image, allocation) };
+            Err(result.into())
+        }
     }
 }
 
+pub unsafe fn create_swapchain(
+    device: &Device,
+    swapchain_loader: &Swapchain,
+    surface: vk::SurfaceKHR,
+    extent: vk::Extent2D,
+    surface_format: vk::SurfaceFormatKHR,
+    present_mode: vk::PresentModeKHR,
+    min_image_count: u32,
+    old_swapchain: vk::SwapchainKHR,
+) -> Result<(vk::SwapchainKHR, Vec<vk::Image>, Vec<vk::ImageView>)> {
+    let swapchain_create_info = vk::SwapchainCreateInfoKHR::default()
+        .surface(surface)
+        .min_image_count(min_image_count)
+        .image_format(surface_format.format)
+        .image_color_space(surface_format.color_space)
+        .image_extent(extent)
+        .image_array_layers(1)
+        .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+        .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+        .pre_transform(vk::SurfaceTransformFlagsKHR::IDENTITY)
+        .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+        .present_mode(present_mode)
+        .old_swapchain(old_swapchain);
+
+    let swapchain = swapchain_loader.create_swapchain(&swapchain_create_info, None)?;
+    let swapchain_images = match swapchain_loader.get_swapchain_images(swapchain) {
+        Ok(swapchain_images) => swapchain_images,
+        Err(result) => {
+            swapchain_loader.destroy_swapchain(swapchain, None);
+            return Err(result.into());
+        }
+    };
+
+    let mut swapchain_image_views = Vec::with_capacity(swapchain_images.len());
+    for image in &swapchain_images {
+        let image_view_create_info = vk::ImageViewCreateInfo::default()
+            .image(*image)
+            .view_type(vk::ImageViewType::TYPE_2D)
+            .format(surface_format.format)
+            .components(Default::default())
+            .subresource_range(
+                vk::ImageSubresourceRange::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .level_count(1)
+                    .layer_count(1),
+            );
+
+        match device.create_image_view(&image_view_create_info, None) {
+            Ok(image_view) => swapchain_image_views.push(image_view),
+            Err(result) => {
+                swapchain_image_views
+                    .iter()
+                    .for_each(|image_view| device.destroy_image_view(*image_view, None));
+                swapchain_loader.destroy_swapchain(swapchain, None);
+                return Err(result.into());
+            }
+        }
+    }
+
+    Ok((swapchain, swapchain_images, swapchain_image_views))
+}
+
+pub fn create_shader_module_from_spirv(device: &Device, code: &[u32]) -> Result<vk::ShaderModule> {
+    let shader_module_create_info = vk::ShaderModuleCreateInfo::default().code(code);
+
+    unsafe { Ok(device.create_shader_module(&shader_module_create_info, None)?) }
+}
+
 pub unsafe fn create_mesh_pipeline(
     device: &Device,
+    pipeline_cache: vk::PipelineCache,
+    mesh_shader_variant: MeshShaderVariant,
     mesh_shader: vk::ShaderModule,
     task_shader: Option<vk::ShaderModule>,
     fragment_shader: vk::ShaderModule,
     layout: vk::PipelineLayout,
 ) -> Result<vk::Pipeline> {
-    let mut pipeline_rendering_create_info = vk::PipelineRenderingCreateInfo::default()
-        .color_attachment_formats(&[SWAPCHAIN_FORMAT])
-        .depth_attachment_format(DEPTH_FORMAT);
-
     let mut shader_stage_create_infos = vec![
         vk::PipelineShaderStageCreateInfo::default()
-            .stage(vk::ShaderStageFlags::MESH_NV)
+            .stage(mesh_shader_variant.mesh_stage())
             .module(mesh_shader)
             .name(CStr::from_bytes_with_nul_unchecked(b"main\0")),
         vk::PipelineShaderStageCreateInfo::default()
@@ -81,12 +260,120 @@ pub unsafe fn create_mesh_pipeline(
     if let Some(task_shader) = task_shader {
         shader_stage_create_infos.push(
             vk::PipelineShaderStageCreateInfo::default()
-                .stage(vk::ShaderStageFlags::TASK_NV)
+                .stage(mesh_shader_variant.task_stage())
                 .module(task_shader)
                 .name(CStr::from_bytes_with_nul_unchecked(b"main\0")),
         )
     }
 
+    create_graphics_pipeline(
+        device,
+        pipeline_cache,
+        &shader_stage_create_infos,
+        None,
+        layout,
+        SCENE_COLOR_FORMAT,
+        DEPTH_FORMAT,
+    )
+}
+
+pub unsafe fn create_vertex_pipeline(
+    device: &Device,
+    pipeline_cache: vk::PipelineCache,
+    vertex_shader: vk::ShaderModule,
+    fragment_shader: vk::ShaderModule,
+    layout: vk::PipelineLayout,
+) -> Result<vk::Pipeline> {
+    let shader_stage_create_infos = [
+        vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::VERTEX)
+            .module(vertex_shader)
+            .name(CStr::from_bytes_with_nul_unchecked(b"main\0")),
+        vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::FRAGMENT)
+            .module(fragment_shader)
+            .name(CStr::from_bytes_with_nul_unchecked(b"main\0")),
+    ];
+
+    let vertex_binding_description = vk::VertexInputBindingDescription::default()
+        .stride(mem::size_of::<Vertex>() as u32)
+        .input_rate(vk::VertexInputRate::VERTEX);
+
+    let vertex_attribute_descriptions = [
+        vk::VertexInputAttributeDescription::default()
+            .location(0)
+            .format(vk::Format::R32G32B32_SFLOAT)
+            .offset(0),
+        vk::VertexInputAttributeDescription::default()
+            .location(1)
+            .format(vk::Format::R32G32B32_SFLOAT)
+            .offset(12),
+        vk::VertexInputAttributeDescription::default()
+            .location(2)
+            .format(vk::Format::R32G32_SFLOAT)
+            .offset(24),
+    ];
+
+    let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::default()
+        .vertex_binding_descriptions(slice::from_ref(&vertex_binding_description))
+        .vertex_attribute_descriptions(&vertex_attribute_descriptions);
+
+    create_graphics_pipeline(
+        device,
+        pipeline_cache,
+        &shader_stage_create_infos,
+        Some(&vertex_input_state_create_info),
+        layout,
+        SCENE_COLOR_FORMAT,
+        DEPTH_FORMAT,
+    )
+}
+
+//the vertex shader generates the triangle from the vertex index, there is no depth attachment
+pub unsafe fn create_fullscreen_pipeline(
+    device: &Device,
+    pipeline_cache: vk::PipelineCache,
+    vertex_shader: vk::ShaderModule,
+    fragment_shader: vk::ShaderModule,
+    layout: vk::PipelineLayout,
+    color_format: vk::Format,
+) -> Result<vk::Pipeline> {
+    let shader_stage_create_infos = [
+        vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::VERTEX)
+            .module(vertex_shader)
+            .name(CStr::from_bytes_with_nul_unchecked(b"main\0")),
+        vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::FRAGMENT)
+            .module(fragment_shader)
+            .name(CStr::from_bytes_with_nul_unchecked(b"main\0")),
+    ];
+
+    create_graphics_pipeline(
+        device,
+        pipeline_cache,
+        &shader_stage_create_infos,
+        Some(&vk::PipelineVertexInputStateCreateInfo::default()),
+        layout,
+        color_format,
+        vk::Format::UNDEFINED,
+    )
+}
+
+//vertex input state must be omitted for mesh pipelines, the depth state is ignored if depth_format is UNDEFINED
+unsafe fn create_graphics_pipeline(
+    device: &Device,
+    pipeline_cache: vk::PipelineCache,
+    shader_stage_create_infos: &[vk::PipelineShaderStageCreateInfo],
+    vertex_input_state_create_info: Option<&vk::PipelineVertexInputStateCreateInfo>,
+    layout: vk::PipelineLayout,
+    color_format: vk::Format,
+    depth_format: vk::Format,
+) -> Result<vk::Pipeline> {
+    let mut pipeline_rendering_create_info = vk::PipelineRenderingCreateInfo::default()
+        .color_attachment_formats(slice::from_ref(&color_format))
+        .depth_attachment_format(depth_format);
+
     let input_assembly_state_create_info = vk::PipelineInputAssemblyStateCreateInfo::default()
         .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
 
@@ -125,8 +412,8 @@ pub unsafe fn create_mesh_pipeline(
     let dynamic_state_create_info =
         vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
 
-    let graphics_pipeline_create_info = vk::GraphicsPipelineCreateInfo::default()
-        .stages(&shader_stage_create_infos)
+    let mut graphics_pipeline_create_info = vk::GraphicsPipelineCreateInfo::default()
+        .stages(shader_stage_create_infos)
         .input_assembly_state(&input_assembly_state_create_info)
         .viewport_state(&viewport_state_create_info)
         .rasterization_state(&rasterization_state_create_info)
@@ -136,12 +423,16 @@ pub unsafe fn create_mesh_pipeline(
         .dynamic_state(&dynamic_state_create_info)
         .layout(layout)
         .push_next(&mut pipeline_rendering_create_info);
+    if let Some(vertex_input_state_create_info) = vertex_input_state_create_info {
+        graphics_pipeline_create_info =
+            graphics_pipeline_create_info.vertex_input_state(vertex_input_state_create_info);
+    }
 
     Ok(device
         .create_graphics_pipelines(
-            vk::PipelineCache::null(),
+            pipeline_cache,
             slice::from_ref(&graphics_pipeline_create_info),
             None,
         )
-        .unwrap()[0])
+        .map_err(|(_, result)| result)?[0])
 }
@@ -0,0 +1,222 @@
+use std::{
+    collections::VecDeque,
+    fmt, thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{ensure, Result};
+
+//number of frames the min/max/percentile statistics are computed over
+pub const DEFAULT_WINDOW_SIZE: usize = 240;
+//weight of the newest frame in the exponential moving average the smoothed fps are based on
+pub const FPS_SMOOTHING: f32 = 0.05;
+
+pub struct FrameTimer {
+    last_frame: Instant,
+    delta: Duration,
+    smoothed_delta: f32,
+
+    frame_times: VecDeque<Duration>,
+    window_size: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    pub smoothed_fps: f32,
+    pub average: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+impl FrameTimer {
+    pub fn new(window_size: usize) -> Self {
+        assert!(window_size > 0);
+
+        Self {
+            last_frame: Instant::now(),
+            delta: Duration::ZERO,
+            smoothed_delta: 0.0,
+
+            frame_times: VecDeque::with_capacity(window_size),
+            window_size,
+        }
+    }
+
+    //call once per frame, returns the time since the previous call in seconds
+    pub fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let delta = now - self.last_frame;
+        self.last_frame = now;
+
+        self.record(delta);
+        self.delta_seconds()
+    }
+
+    //adds a frame time without measuring it, tick does this with the time since the previous tick
+    pub fn record(&mut self, delta: Duration) {
+        self.delta = delta;
+
+        let delta = delta.as_secs_f32();
+        self.smoothed_delta = if self.frame_times.is_empty() {
+            delta
+        } else {
+            self.smoothed_delta + (delta - self.smoothed_delta) * FPS_SMOOTHING
+        };
+
+        if self.frame_times.len() == self.window_size {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(self.delta);
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        if self.frame_times.is_empty() {
+            return FrameStats::default();
+        }
+
+        let mut sorted: Vec<_> = self.frame_times.iter().copied().collect();
+        sorted.sort_unstable();
+
+        //nearest rank
+        let percentile = |p: f32| {
+            let rank = (p / 100.0 * sorted.len() as f32).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        FrameStats {
+            smoothed_fps: if self.smoothed_delta > 0.0 {
+                1.0 / self.smoothed_delta
+            } else {
+                0.0
+            },
+            average: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        }
+    }
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_SIZE)
+    }
+}
+
+//short enough for a window title
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+        write!(
+            f,
+            "{:.1} fps | {:.2} ms (min {:.2}, max {:.2}, p95 {:.2}, p99 {:.2})",
+            self.smoothed_fps,
+            ms(self.average),
+            ms(self.min),
+            ms(self.max),
+            ms(self.p95),
+            ms(self.p99)
+        )
+    }
+}
+
+//accumulates variable frame times and runs simulation updates in steps of a fixed size
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+    //bounds the work after a long stall instead of trying to catch up on all of it
+    max_steps_per_frame: u32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: Duration, max_steps_per_frame: u32) -> Self {
+        assert!(step > Duration::ZERO && max_steps_per_frame > 0);
+
+        Self {
+            step,
+            accumulator: Duration::ZERO,
+            max_steps_per_frame,
+        }
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    //returns how many simulation steps to run this frame, time beyond max_steps_per_frame steps is dropped
+    pub fn accumulate(&mut self, delta: Duration) -> u32 {
+        self.accumulator += delta;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+
+            if steps == self.max_steps_per_frame {
+                self.accumulator = Duration::from_nanos(
+                    (self.accumulator.as_nanos() % self.step.as_nanos()) as u64,
+                );
+                break;
+            }
+        }
+
+        steps
+    }
+
+    //how far the simulation is into the next step, 0 to 1, for interpolating between the last two states
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.step.as_secs_f64()).min(1.0) as f32
+    }
+}
+
+//caps the frame rate by sleeping, for present modes that do not wait for vblank
+pub struct FrameLimiter {
+    frame_time: Duration,
+    next_frame: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(max_fps: f32) -> Result<Self> {
+        //tiny and subnormal limits give frame times Duration can not represent
+        let frame_seconds = 1.0 / max_fps;
+        ensure!(
+            max_fps.is_finite()
+                && max_fps > 0.0
+                && frame_seconds.is_finite()
+                && frame_seconds < Duration::MAX.as_secs_f32(),
+            "The frame rate limit must be a positive number, not {}",
+            max_fps
+        );
+
+        Ok(Self {
+            frame_time: Duration::from_secs_f32(frame_seconds),
+            next_frame: Instant::now(),
+        })
+    }
+
+    //call once per frame, sleeps until the frame is due
+    pub fn wait(&mut self) {
+        let now = Instant::now();
+        if now < self.next_frame {
+            thread::sleep(self.next_frame - now);
+            self.next_frame += self.frame_time;
+        } else {
+            //behind schedule, e.g. after a stall, frames are not rushed to catch up
+            self.next_frame = now + self.frame_time;
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use std::{collections::HashSet, env, fs, path::PathBuf};
+
+use ash::{extensions::khr::Surface, vk, Entry};
+use glam::Vec3;
+use vulkan_experinments::{
+    asset::{image::Image, Mesh},
+    render::{config::RenderConfig, device_selection, render_ctx::RenderCtx, renderer, Camera},
+};
+
+pub const WIDTH: u32 = 256;
+pub const HEIGHT: u32 = 256;
+
+//set to rewrite the reference images with the current output instead of comparing against them
+pub const UPDATE_ENV_VAR: &str = "VULKAN_EXPERIMENTS_UPDATE_GOLDEN";
+
+//returns early from the calling test if there is nothing to render with, e.g. on ci machines without an icd
+macro_rules! require_vulkan {
+    () => {
+        if let Some(reason) = $crate::common::vulkan_unavailable_reason() {
+            eprintln!("skipping, Vulkan is unavailable: {}", reason);
+            return;
+        }
+    };
+}
+pub(crate) use require_vulkan;
+
+pub fn vulkan_unavailable_reason() -> Option<String> {
+    unsafe {
+        let entry_loader = match Entry::load() {
+            Ok(entry_loader) => entry_loader,
+            Err(e) => return Some(format!("failed to load the Vulkan loader: {}", e)),
+        };
+
+        let application_info = vk::ApplicationInfo::default().api_version(vk::API_VERSION_1_3);
+        let instance_create_info =
+            vk::InstanceCreateInfo::default().application_info(&application_info);
+        let instance_loader = match entry_loader.create_instance(&instance_create_info, None) {
+            Ok(instance_loader) => instance_loader,
+            Err(result) => return Some(format!("failed to create an instance: {}", result)),
+        };
+
+        //the same selection RenderCtx::new_headless performs
+        let surface_loader = Surface::new(&entry_loader, &instance_loader);
+        let reason = device_selection::select_physical_device(
+            &instance_loader,
+            &surface_loader,
+            None,
+            &[],
+            RenderConfig::from_env().device.as_ref(),
+        )
+        .err()
+        .map(|e| e.to_string());
+
+        instance_loader.destroy_instance(None);
+
+        reason
+    }
+}
+
+//a camera at position looking at target
+pub fn camera_looking_at(position: Vec3, target: Vec3) -> Camera {
+    let direction = (target - position).normalize();
+    let rotation = Vec3::new(direction.x.atan2(direction.z), direction.y.asin(), 0.0);
+
+    let mut camera = Camera::new(position, rotation);
+    camera.set_viewport_size(WIDTH, HEIGHT);
+    camera.update(&HashSet::new(), 0.0);
+    camera
+}
+
+pub fn render(mesh: &Mesh, camera: &Camera) -> Image {
+    //validated whenever the layer is installed, any error fails the test
+    let mut config = RenderConfig {
+        validation: true,
+        panic_on_validation_error: true,
+        //keeps the device from the environment
+        ..RenderConfig::from_env()
+    };
+
+    //passed explicitly, changing the working directory would race with the other tests
+    if cfg!(feature = "shaders-from-disk") {
+        let bin_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("bin");
+        assert!(
+            bin_dir.join("example.frag.spv").exists(),
+            "{} contains no shaders, run shaders/compile-shaders.sh first",
+            bin_dir.display()
+        );
+        config.shader_directory = bin_dir;
+    }
+
+    let mut render_ctx = RenderCtx::new_headless(WIDTH, HEIGHT, &config).unwrap();
+    render_ctx.set_mesh(mesh).unwrap();
+
+    let pixels = unsafe { renderer::render_offscreen(&render_ctx, camera).unwrap() };
+    Image::new(WIDTH, HEIGHT, pixels).unwrap()
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance {
+    //pixels with any channel differing by more than this count as mismatched
+    pub channel_threshold: u8,
+    //fraction of mismatched pixels that is still accepted, 0 to 1
+    pub max_mismatched_fraction: f64,
+    pub min_psnr: f64,
+}
+
+impl Default for Tolerance {
+    //loose enough to absorb rasterization differences between drivers
+    fn default() -> Self {
+        Self {
+            channel_threshold: 8,
+            max_mismatched_fraction: 0.005,
+            min_psnr: 35.0,
+        }
+    }
+}
+
+pub struct Comparison {
+    pub mismatched_pixels: usize,
+    pub total_pixels: usize,
+    //infinite for identical images
+    pub psnr: f64,
+    //mismatched pixels in red on top of the dimmed reference
+    pub diff: Image,
+}
+
+impl Comparison {
+    pub fn mismatched_fraction(&self) -> f64 {
+        self.mismatched_pixels as f64 / self.total_pixels as f64
+    }
+
+    pub fn is_within(&self, tolerance: &Tolerance) -> bool {
+        self.mismatched_fraction() <= tolerance.max_mismatched_fraction
+            && self.psnr >= tolerance.min_psnr
+    }
+}
+
+//both images must have the same size
+pub fn compare(actual: &Image, expected: &Image, channel_threshold: u8) -> Comparison {
+    assert_eq!(
+        (actual.width, actual.height),
+        (expected.width, expected.height)
+    );
+
+    let mut mismatched_pixels = 0;
+    let mut squared_error_sum = 0.0;
+    let mut diff_pixels = Vec::with_capacity(expected.pixels.len());
+
+    for (actual, expected) in actual
+        .pixels
+        .chunks_exact(4)
+        .zip(expected.pixels.chunks_exact(4))
+    {
+        let mut is_mismatched = false;
+        for (&a, &e) in actual.iter().zip(expected) {
+            let difference = a.abs_diff(e);
+            is_mismatched |= difference > channel_threshold;
+            squared_error_sum += (difference as f64).powi(2);
+        }
+
+        if is_mismatched {
+            mismatched_pixels += 1;
+            diff_pixels.extend([255, 0, 0, 255]);
+        } else {
+            let luminance = (expected[0] as u32 + expected[1] as u32 + expected[2] as u32) / 3;
+            let dimmed = (luminance / 4) as u8;
+            diff_pixels.extend([dimmed, dimmed, dimmed, 255]);
+        }
+    }
+
+    let mean_squared_error = squared_error_sum / expected.pixels.len() as f64;
+    let psnr = 10.0 * (255.0f64.powi(2) / mean_squared_error).log10();
+
+    Comparison {
+        mismatched_pixels,
+        total_pixels: expected.pixels.len() / 4,
+        psnr,
+        diff: Image::new(expected.width, expected.height, diff_pixels).unwrap(),
+    }
+}
+
+//compares against tests/golden/<name>.png, the output and a diff image are written to the target directory on failure
+//a missing reference fails the test rather than skipping it, so a test can not pass without ever being compared,
+//new references are created with VULKAN_EXPERIMENTS_UPDATE_GOLDEN=1 and have to be reviewed before committing them
+pub fn assert_matches_golden(name: &str, actual: &Image, tolerance: &Tolerance) {
+    let golden_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.png", name));
+
+    if env::var_os(UPDATE_ENV_VAR).is_some() {
+        fs::create_dir_all(golden_path.parent().unwrap()).unwrap();
+        actual.save(&golden_path).unwrap();
+        eprintln!("updated {}", golden_path.display());
+        return;
+    }
+
+    let output_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    fs::create_dir_all(&output_dir).unwrap();
+    let actual_path = output_dir.join(format!("{}.actual.png", name));
+    let diff_path = output_dir.join(format!("{}.diff.png", name));
+
+    if !golden_path.exists() {
+        actual.save(&actual_path).unwrap();
+        panic!(
+            "{}: there is no reference image at {}\nthe output was written to {}, review it and rerun with {}=1 to accept it",
+            name,
+            golden_path.display(),
+            actual_path.display(),
+            UPDATE_ENV_VAR
+        );
+    }
+
+    let expected = match Image::load(&golden_path) {
+        Ok(expected) => expected,
+        Err(e) => {
+            actual.save(&actual_path).unwrap();
+            panic!(
+                "{}: {:#}\nthe output was written to {}, rerun with {}=1 to replace it",
+                name,
+                e,
+                actual_path.display(),
+                UPDATE_ENV_VAR
+            );
+        }
+    };
+
+    if (actual.width, actual.height) != (expected.width, expected.height) {
+        actual.save(&actual_path).unwrap();
+        panic!(
+            "{}: expected a {}x{} image, got {}x{}, the output was written to {}",
+            name,
+            expected.width,
+            expected.height,
+            actual.width,
+            actual.height,
+            actual_path.display()
+        );
+    }
+
+    let comparison = compare(actual, &expected, tolerance.channel_threshold);
+    if !comparison.is_within(tolerance) {
+        actual.save(&actual_path).unwrap();
+        comparison.diff.save(&diff_path).unwrap();
+        panic!(
+            "{}: {} of {} pixels ({:.3}%) differ by more than {}, psnr {:.2} dB, tolerance {:?}\noutput: {}\ndiff: {}",
+            name,
+            comparison.mismatched_pixels,
+            comparison.total_pixels,
+            comparison.mismatched_fraction() * 100.0,
+            tolerance.channel_threshold,
+            comparison.psnr,
+            tolerance,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
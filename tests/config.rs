@@ -0,0 +1,116 @@
+use ash::vk;
+use vulkan_experinments::render::{
+    config::{self, PresentMode, RenderConfig},
+    device_selection::DevicePreference,
+    error::RenderCtxError,
+    render_ctx::RenderCtx,
+};
+
+#[test]
+fn preferred_present_mode_is_used_when_supported() {
+    let supported = [
+        vk::PresentModeKHR::FIFO,
+        vk::PresentModeKHR::FIFO_RELAXED,
+        vk::PresentModeKHR::MAILBOX,
+        vk::PresentModeKHR::IMMEDIATE,
+    ];
+
+    for present_mode in [
+        PresentMode::Fifo,
+        PresentMode::FifoRelaxed,
+        PresentMode::Mailbox,
+        PresentMode::Immediate,
+    ] {
+        assert_eq!(
+            config::choose_present_mode(present_mode, &supported),
+            present_mode.to_vk()
+        );
+    }
+}
+
+#[test]
+fn uncapped_present_modes_fall_back_to_each_other() {
+    assert_eq!(
+        config::choose_present_mode(
+            PresentMode::Mailbox,
+            &[vk::PresentModeKHR::FIFO, vk::PresentModeKHR::IMMEDIATE]
+        ),
+        vk::PresentModeKHR::IMMEDIATE
+    );
+    assert_eq!(
+        config::choose_present_mode(
+            PresentMode::Immediate,
+            &[vk::PresentModeKHR::FIFO, vk::PresentModeKHR::MAILBOX]
+        ),
+        vk::PresentModeKHR::MAILBOX
+    );
+}
+
+#[test]
+fn unsupported_present_modes_fall_back_to_fifo() {
+    let supported = [vk::PresentModeKHR::FIFO];
+
+    assert_eq!(
+        config::choose_present_mode(PresentMode::FifoRelaxed, &supported),
+        vk::PresentModeKHR::FIFO
+    );
+    assert_eq!(
+        config::choose_present_mode(PresentMode::Mailbox, &supported),
+        vk::PresentModeKHR::FIFO
+    );
+}
+
+#[test]
+fn present_modes_round_trip_through_their_names() {
+    for present_mode in [
+        PresentMode::Fifo,
+        PresentMode::FifoRelaxed,
+        PresentMode::Mailbox,
+        PresentMode::Immediate,
+    ] {
+        assert_eq!(
+            present_mode.to_string().parse::<PresentMode>().unwrap(),
+            present_mode
+        );
+    }
+    assert!("vsync".parse::<PresentMode>().is_err());
+}
+
+#[test]
+fn image_count_is_one_above_the_minimum_within_the_maximum() {
+    let capabilities = |min_image_count, max_image_count| vk::SurfaceCapabilitiesKHR {
+        min_image_count,
+        max_image_count,
+        ..Default::default()
+    };
+
+    assert_eq!(config::choose_image_count(&capabilities(2, 8)), 3);
+    assert_eq!(config::choose_image_count(&capabilities(3, 3)), 3);
+    //0 means there is no maximum
+    assert_eq!(config::choose_image_count(&capabilities(2, 0)), 3);
+}
+
+//rejected before vulkan is loaded, so this runs without a driver
+#[test]
+fn zero_frames_in_flight_are_rejected() {
+    let config = RenderConfig {
+        frames_in_flight: 0,
+        ..Default::default()
+    };
+
+    let error = RenderCtx::new_headless(64, 64, &config).err().unwrap();
+    assert!(
+        matches!(error, RenderCtxError::InvalidConfig(_)),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn device_preference_is_an_index_or_a_name() {
+    assert_eq!("1".parse(), Ok(DevicePreference::Index(1)));
+    assert_eq!(
+        "GeForce RTX".parse(),
+        Ok(DevicePreference::Name("geforce rtx".to_owned()))
+    );
+}
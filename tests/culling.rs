@@ -0,0 +1,193 @@
+use glam::{Mat4, Vec3, Vec4};
+use vulkan_experinments::render::{
+    culling::{self, Frustum},
+    meshlet::MeshletBounds,
+};
+
+//with an identity matrix the frustum is the clip space box, -1 <= x, y <= 1 and 0 <= z <= 1
+fn unit_frustum() -> Frustum {
+    Frustum::from_matrix(&Mat4::IDENTITY)
+}
+
+fn bounds(center: Vec3, radius: f32, cone_axis: Vec3, cone_cutoff: f32) -> MeshletBounds {
+    MeshletBounds {
+        center,
+        radius,
+        cone_axis,
+        cone_cutoff,
+    }
+}
+
+#[test]
+fn planes_are_normalized_and_point_inwards() {
+    let frustum = unit_frustum();
+
+    assert_eq!(
+        frustum.planes,
+        [
+            Vec4::new(1.0, 0.0, 0.0, 1.0),
+            Vec4::new(-1.0, 0.0, 0.0, 1.0),
+            Vec4::new(0.0, 1.0, 0.0, 1.0),
+            Vec4::new(0.0, -1.0, 0.0, 1.0),
+            Vec4::new(0.0, 0.0, 1.0, 0.0),
+            Vec4::new(0.0, 0.0, -1.0, 1.0),
+        ]
+    );
+
+    let projection = Mat4::perspective_rh(1.0, 1.5, 0.1, 100.0);
+    let view = Mat4::look_at_rh(Vec3::new(1.0, 2.0, 3.0), Vec3::ZERO, Vec3::Y);
+    for plane in Frustum::from_matrix(&(projection * view)).planes {
+        assert!((plane.truncate().length() - 1.0).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn sphere_inside_is_visible() {
+    let frustum = unit_frustum();
+
+    assert!(frustum.is_sphere_visible(Vec3::new(0.0, 0.0, 0.5), 0.25));
+    //a point exactly on a plane counts as inside
+    assert!(frustum.is_sphere_visible(Vec3::new(1.0, 1.0, 1.0), 0.0));
+}
+
+#[test]
+fn sphere_outside_any_plane_is_culled() {
+    let frustum = unit_frustum();
+    let offsets = [
+        Vec3::new(-1.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, -1.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 0.0, 1.0),
+    ];
+
+    for offset in offsets {
+        //the frustum center is (0, 0, 0.5), every plane is at least 0.5 away from it
+        let center = Vec3::new(0.0, 0.0, 0.5) + offset * 1.5;
+        assert!(!frustum.is_sphere_visible(center, 0.25), "{}", offset);
+    }
+}
+
+#[test]
+fn sphere_straddling_any_plane_is_visible() {
+    let frustum = unit_frustum();
+    let centers = [
+        Vec3::new(-1.1, 0.0, 0.5),
+        Vec3::new(1.1, 0.0, 0.5),
+        Vec3::new(0.0, -1.1, 0.5),
+        Vec3::new(0.0, 1.1, 0.5),
+        Vec3::new(0.0, 0.0, -0.1),
+        Vec3::new(0.0, 0.0, 1.1),
+    ];
+
+    for center in centers {
+        assert!(frustum.is_sphere_visible(center, 0.25), "{}", center);
+        assert!(!frustum.is_sphere_visible(center, 0.05), "{}", center);
+    }
+}
+
+#[test]
+fn perspective_near_plane_culls_spheres_behind_it() {
+    let frustum = Frustum::from_matrix(&Mat4::perspective_rh(1.0, 1.0, 0.1, 100.0));
+
+    assert!(frustum.is_sphere_visible(Vec3::new(0.0, 0.0, -1.0), 0.1));
+    assert!(!frustum.is_sphere_visible(Vec3::new(0.0, 0.0, -0.05), 0.01));
+    assert!(!frustum.is_sphere_visible(Vec3::new(0.0, 0.0, -101.0), 0.5));
+}
+
+#[test]
+fn degenerate_cone_is_never_back_facing() {
+    let meshlet = bounds(Vec3::ZERO, 1.0, Vec3::ZERO, 1.0);
+
+    for camera_position in [Vec3::X * 10.0, Vec3::NEG_X * 10.0, Vec3::Z * 1000.0] {
+        assert!(!culling::is_cone_back_facing(&meshlet, camera_position));
+    }
+
+    //even an axis with a cutoff above one must not cull anything
+    let meshlet = bounds(Vec3::ZERO, 0.0, Vec3::Z, 1.5);
+    assert!(!culling::is_cone_back_facing(&meshlet, Vec3::NEG_Z * 10.0));
+}
+
+#[test]
+fn cone_facing_away_from_the_camera_is_back_facing() {
+    //every triangle faces +z within 30 degrees, the cutoff is the sine of the half angle
+    let meshlet = bounds(Vec3::ZERO, 1.0, Vec3::Z, 0.5);
+
+    assert!(culling::is_cone_back_facing(&meshlet, Vec3::NEG_Z * 10.0));
+    //slightly off axis, still looking at the back of every triangle
+    assert!(culling::is_cone_back_facing(
+        &meshlet,
+        Vec3::new(1.0, 0.0, -10.0)
+    ));
+}
+
+#[test]
+fn cone_facing_the_camera_is_not_back_facing() {
+    let meshlet = bounds(Vec3::ZERO, 1.0, Vec3::Z, 0.5);
+
+    assert!(!culling::is_cone_back_facing(&meshlet, Vec3::Z * 10.0));
+    //from the side some triangles may be front facing
+    assert!(!culling::is_cone_back_facing(&meshlet, Vec3::X * 10.0));
+    //close to the meshlet the bounding sphere keeps it visible
+    assert!(!culling::is_cone_back_facing(&meshlet, Vec3::NEG_Z * 1.5));
+}
+
+#[test]
+fn meshlet_visibility_combines_both_tests() {
+    let frustum = unit_frustum();
+    let camera_position = Vec3::new(0.0, 0.0, -10.0);
+
+    let inside = Vec3::new(0.0, 0.0, 0.5);
+    let outside = Vec3::new(5.0, 0.0, 0.5);
+
+    assert!(culling::is_meshlet_visible(
+        &bounds(inside, 0.1, Vec3::NEG_Z, 0.5),
+        &frustum,
+        camera_position
+    ));
+    assert!(!culling::is_meshlet_visible(
+        &bounds(inside, 0.1, Vec3::Z, 0.5),
+        &frustum,
+        camera_position
+    ));
+    assert!(!culling::is_meshlet_visible(
+        &bounds(outside, 0.1, Vec3::NEG_Z, 0.5),
+        &frustum,
+        camera_position
+    ));
+    assert!(culling::is_meshlet_visible(
+        &bounds(inside, 0.1, Vec3::ZERO, 1.0),
+        &frustum,
+        camera_position
+    ));
+}
+
+#[test]
+fn bounds_follow_the_draw_transform() {
+    let cone = bounds(Vec3::new(1.0, 0.0, 0.0), 0.5, Vec3::Z, 0.5);
+    let transform = Mat4::from_translation(Vec3::new(0.0, 2.0, 0.0))
+        * Mat4::from_rotation_y(std::f32::consts::FRAC_PI_2)
+        * Mat4::from_scale(Vec3::splat(2.0));
+
+    let transformed = culling::transform_bounds(&cone, &transform);
+    assert!(transformed
+        .center
+        .abs_diff_eq(Vec3::new(0.0, 2.0, -2.0), 1e-5));
+    assert!((transformed.radius - 1.0).abs() < 1e-6);
+    assert!(transformed.cone_axis.abs_diff_eq(Vec3::X, 1e-5));
+    assert_eq!(transformed.cone_cutoff, 0.5);
+}
+
+#[test]
+fn non_uniform_scale_disables_cone_culling() {
+    let cone = bounds(Vec3::ZERO, 1.0, Vec3::Z, 0.5);
+
+    let transformed = culling::transform_bounds(&cone, &Mat4::from_scale(Vec3::new(1.0, 3.0, 1.0)));
+    assert_eq!(transformed.radius, 3.0);
+    assert_eq!(transformed.cone_cutoff, 1.0);
+    assert!(!culling::is_cone_back_facing(
+        &transformed,
+        Vec3::new(0.0, 0.0, -10.0)
+    ));
+}
@@ -0,0 +1,206 @@
+#hand assembles the spir-v fixtures of tests/reflection.rs, no glslang required
+#run from this directory, the equivalent glsl is noted above each module
+import struct
+
+MAGIC = 0x07230203
+
+OP_CAPABILITY = 17
+OP_EXTENSION = 10
+OP_MEMORY_MODEL = 14
+OP_ENTRY_POINT = 15
+OP_EXECUTION_MODE = 16
+OP_NAME = 5
+OP_DECORATE = 71
+OP_MEMBER_DECORATE = 72
+OP_TYPE_VOID = 19
+OP_TYPE_INT = 21
+OP_TYPE_FLOAT = 22
+OP_TYPE_VECTOR = 23
+OP_TYPE_MATRIX = 24
+OP_TYPE_IMAGE = 25
+OP_TYPE_SAMPLED_IMAGE = 27
+OP_TYPE_ARRAY = 28
+OP_TYPE_RUNTIME_ARRAY = 29
+OP_TYPE_STRUCT = 30
+OP_TYPE_POINTER = 32
+OP_TYPE_FUNCTION = 33
+OP_CONSTANT = 43
+OP_FUNCTION = 54
+OP_FUNCTION_END = 56
+OP_VARIABLE = 59
+OP_LABEL = 248
+OP_RETURN = 253
+
+BLOCK = 2
+ARRAY_STRIDE = 6
+MATRIX_STRIDE = 7
+COL_MAJOR = 5
+BINDING = 33
+DESCRIPTOR_SET = 34
+OFFSET = 35
+
+UNIFORM_CONSTANT = 0
+UNIFORM = 2
+PUSH_CONSTANT = 9
+STORAGE_BUFFER = 12
+
+FRAGMENT = 4
+MESH_EXT = 5365
+
+
+def string(text):
+    data = text.encode() + b"\0"
+    data += b"\0" * (-len(data) % 4)
+    return list(struct.unpack("<%dI" % (len(data) // 4), data))
+
+
+def op(opcode, *operands):
+    words = []
+    for operand in operands:
+        words += string(operand) if isinstance(operand, str) else [operand]
+    return [(len(words) + 1) << 16 | opcode] + words
+
+
+def module(version, bound, *instructions):
+    words = [MAGIC, version, 0, bound, 0]
+    for instruction in instructions:
+        words += instruction
+    return struct.pack("<%dI" % len(words), *words)
+
+
+def function(void, function_type, id, label):
+    return [
+        op(OP_FUNCTION, void, id, 0, function_type),
+        op(OP_LABEL, label),
+        op(OP_RETURN),
+        op(OP_FUNCTION_END),
+    ]
+
+
+def block(struct_id, offsets):
+    return [op(OP_DECORATE, struct_id, BLOCK)] + [
+        op(OP_MEMBER_DECORATE, struct_id, index, OFFSET, offset)
+        for index, offset in enumerate(offsets)
+    ]
+
+
+#layout(local_size_x = 32) in;
+#layout(triangles, max_vertices = 64, max_primitives = 126) out;
+#layout(set = 0, binding = 0) uniform UBO { mat4 view_projection; } ubo;
+#layout(set = 0, binding = 1) readonly buffer Vertices { vec4 vertices[]; };
+#layout(push_constant) uniform PushConstants { vec4 tint; uint meshlet_offset; } pc;
+mesh = module(
+    0x00010400,
+    19,
+    op(OP_CAPABILITY, 5283),
+    op(OP_EXTENSION, "SPV_EXT_mesh_shader"),
+    op(OP_MEMORY_MODEL, 0, 1),
+    op(OP_ENTRY_POINT, MESH_EXT, 1, "main", 10, 14, 17),
+    op(OP_EXECUTION_MODE, 1, 17, 32, 1, 1),
+    op(OP_EXECUTION_MODE, 1, 26, 64),
+    op(OP_EXECUTION_MODE, 1, 5270, 126),
+    op(OP_EXECUTION_MODE, 1, 5298),
+    op(OP_NAME, 10, "ubo"),
+    op(OP_NAME, 14, "vertices"),
+    op(OP_NAME, 17, "pc"),
+    *block(8, [0]),
+    op(OP_MEMBER_DECORATE, 8, 0, COL_MAJOR),
+    op(OP_MEMBER_DECORATE, 8, 0, MATRIX_STRIDE, 16),
+    op(OP_DECORATE, 10, DESCRIPTOR_SET, 0),
+    op(OP_DECORATE, 10, BINDING, 0),
+    op(OP_DECORATE, 11, ARRAY_STRIDE, 16),
+    *block(12, [0]),
+    op(OP_DECORATE, 14, DESCRIPTOR_SET, 0),
+    op(OP_DECORATE, 14, BINDING, 1),
+    *block(15, [0, 16]),
+    op(OP_TYPE_VOID, 2),
+    op(OP_TYPE_FUNCTION, 3, 2),
+    op(OP_TYPE_FLOAT, 4, 32),
+    op(OP_TYPE_VECTOR, 5, 4, 4),
+    op(OP_TYPE_MATRIX, 6, 5, 4),
+    op(OP_TYPE_INT, 7, 32, 0),
+    op(OP_TYPE_STRUCT, 8, 6),
+    op(OP_TYPE_POINTER, 9, UNIFORM, 8),
+    op(OP_VARIABLE, 9, 10, UNIFORM),
+    op(OP_TYPE_RUNTIME_ARRAY, 11, 5),
+    op(OP_TYPE_STRUCT, 12, 11),
+    op(OP_TYPE_POINTER, 13, STORAGE_BUFFER, 12),
+    op(OP_VARIABLE, 13, 14, STORAGE_BUFFER),
+    op(OP_TYPE_STRUCT, 15, 5, 7),
+    op(OP_TYPE_POINTER, 16, PUSH_CONSTANT, 15),
+    op(OP_VARIABLE, 16, 17, PUSH_CONSTANT),
+    *function(2, 3, 1, 18),
+)
+
+
+#layout(set = 0, binding = 0) uniform UBO { vec4 color; } ubo;
+#layout(set = 1, binding = 0) uniform sampler2D textures[4];
+#layout(push_constant) uniform PushConstants { vec4 tint; } pc;
+fragment = module(
+    0x00010000,
+    20,
+    op(OP_CAPABILITY, 1),
+    op(OP_MEMORY_MODEL, 0, 1),
+    op(OP_ENTRY_POINT, FRAGMENT, 1, "main"),
+    op(OP_EXECUTION_MODE, 1, 7),
+    op(OP_NAME, 11, "textures"),
+    op(OP_DECORATE, 11, DESCRIPTOR_SET, 1),
+    op(OP_DECORATE, 11, BINDING, 0),
+    *block(13, [0]),
+    op(OP_DECORATE, 15, DESCRIPTOR_SET, 0),
+    op(OP_DECORATE, 15, BINDING, 0),
+    *block(16, [0]),
+    op(OP_TYPE_VOID, 2),
+    op(OP_TYPE_FUNCTION, 3, 2),
+    op(OP_TYPE_FLOAT, 4, 32),
+    op(OP_TYPE_IMAGE, 5, 4, 1, 0, 0, 0, 1, 0),
+    op(OP_TYPE_SAMPLED_IMAGE, 6, 5),
+    op(OP_TYPE_INT, 7, 32, 0),
+    op(OP_CONSTANT, 7, 8, 4),
+    op(OP_TYPE_ARRAY, 9, 6, 8),
+    op(OP_TYPE_POINTER, 10, UNIFORM_CONSTANT, 9),
+    op(OP_VARIABLE, 10, 11, UNIFORM_CONSTANT),
+    op(OP_TYPE_VECTOR, 12, 4, 4),
+    op(OP_TYPE_STRUCT, 13, 12),
+    op(OP_TYPE_POINTER, 14, UNIFORM, 13),
+    op(OP_VARIABLE, 14, 15, UNIFORM),
+    op(OP_TYPE_STRUCT, 16, 12),
+    op(OP_TYPE_POINTER, 17, PUSH_CONSTANT, 16),
+    op(OP_VARIABLE, 17, 18, PUSH_CONSTANT),
+    *function(2, 3, 1, 19),
+)
+
+
+#layout(set = 0, binding = 1) uniform Vertices { vec4 vertices[16]; };
+#binding 1 is a storage buffer in the mesh shader above
+mismatched_fragment = module(
+    0x00010000,
+    13,
+    op(OP_CAPABILITY, 1),
+    op(OP_MEMORY_MODEL, 0, 1),
+    op(OP_ENTRY_POINT, FRAGMENT, 1, "main"),
+    op(OP_EXECUTION_MODE, 1, 7),
+    op(OP_DECORATE, 8, ARRAY_STRIDE, 16),
+    *block(9, [0]),
+    op(OP_DECORATE, 11, DESCRIPTOR_SET, 0),
+    op(OP_DECORATE, 11, BINDING, 1),
+    op(OP_TYPE_VOID, 2),
+    op(OP_TYPE_FUNCTION, 3, 2),
+    op(OP_TYPE_FLOAT, 4, 32),
+    op(OP_TYPE_VECTOR, 5, 4, 4),
+    op(OP_TYPE_INT, 6, 32, 0),
+    op(OP_CONSTANT, 6, 7, 16),
+    op(OP_TYPE_ARRAY, 8, 5, 7),
+    op(OP_TYPE_STRUCT, 9, 8),
+    op(OP_TYPE_POINTER, 10, UNIFORM, 9),
+    op(OP_VARIABLE, 10, 11, UNIFORM),
+    *function(2, 3, 1, 12),
+)
+
+for name, code in [
+    ("mesh.spv", mesh),
+    ("fragment.spv", fragment),
+    ("mismatched_fragment.spv", mismatched_fragment),
+]:
+    with open(name, "wb") as file:
+        file.write(code)
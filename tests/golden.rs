@@ -0,0 +1,97 @@
+use std::f32::consts::TAU;
+
+use glam::{Vec2, Vec3};
+use vulkan_experinments::asset::{image::Image, Mesh, MeshGroup, Vertex};
+
+mod common;
+
+use common::{require_vulkan, Tolerance};
+
+//large enough to be split into a few dozen meshlets, so culling and meshlet boundaries are exercised
+fn torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> Mesh {
+    let mut mesh = Mesh::default();
+
+    for i in 0..=major_segments {
+        let u = i as f32 / major_segments as f32;
+        let (major_sin, major_cos) = (u * TAU).sin_cos();
+
+        for j in 0..=minor_segments {
+            let v = j as f32 / minor_segments as f32;
+            let (minor_sin, minor_cos) = (v * TAU).sin_cos();
+
+            let normal = Vec3::new(major_cos * minor_cos, minor_sin, major_sin * minor_cos);
+            let center = Vec3::new(major_cos, 0.0, major_sin) * major_radius;
+
+            mesh.vertices.push(Vertex {
+                position: center + normal * minor_radius,
+                normal,
+                tex_coord: Vec2::new(u, v),
+            });
+        }
+    }
+
+    let stride = minor_segments + 1;
+    for i in 0..major_segments {
+        for j in 0..minor_segments {
+            let a = i * stride + j;
+            let b = a + stride;
+            mesh.indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+        }
+    }
+
+    mesh.groups.push(MeshGroup {
+        name: String::from("torus"),
+        index_offset: 0,
+        index_count: mesh.indices.len() as u32,
+    });
+
+    mesh
+}
+
+#[test]
+fn triangle() {
+    require_vulkan!();
+
+    let camera = common::camera_looking_at(Vec3::new(0.0, 0.0, -1.0), Vec3::ZERO);
+    let image = common::render(&Mesh::triangle(), &camera);
+
+    common::assert_matches_golden("triangle", &image, &Tolerance::default());
+}
+
+//devices without mesh shaders render the same geometry through the vertex shader fallback
+#[test]
+fn meshlet_torus_front() {
+    require_vulkan!();
+
+    let camera = common::camera_looking_at(Vec3::new(0.0, 1.5, -3.0), Vec3::ZERO);
+    let image = common::render(&torus(1.0, 0.35, 64, 32), &camera);
+
+    common::assert_matches_golden("meshlet_torus_front", &image, &Tolerance::default());
+}
+
+//close enough that part of the torus is outside the frustum
+#[test]
+fn meshlet_torus_close() {
+    require_vulkan!();
+
+    let camera = common::camera_looking_at(Vec3::new(1.2, 0.6, -1.2), Vec3::new(1.0, 0.0, 0.0));
+    let image = common::render(&torus(1.0, 0.35, 64, 32), &camera);
+
+    common::assert_matches_golden("meshlet_torus_close", &image, &Tolerance::default());
+}
+
+#[test]
+fn compare_counts_mismatched_pixels() {
+    let expected = Image::new(2, 2, vec![100; 16]).unwrap();
+    let mut pixels = vec![100; 16];
+    pixels[0] = 104;
+    pixels[4] = 200;
+    let actual = Image::new(2, 2, pixels).unwrap();
+
+    let comparison = common::compare(&actual, &expected, 8);
+    assert_eq!(comparison.mismatched_pixels, 1);
+    assert_eq!(comparison.diff.pixels[4..8], [255, 0, 0, 255]);
+    assert!(comparison.psnr > 0.0 && comparison.psnr.is_finite());
+
+    assert!(common::compare(&expected, &expected, 0).psnr.is_infinite());
+}
@@ -0,0 +1,163 @@
+use glam::Vec3;
+use vulkan_experinments::render::meshlet::{self, MeshletMesh};
+
+//a flat grid in the xy plane, two counter clockwise triangles per cell
+fn grid(size: u32) -> (Vec<Vec3>, Vec<u32>) {
+    let positions = (0..=size)
+        .flat_map(|y| (0..=size).map(move |x| Vec3::new(x as f32, y as f32, 0.0)))
+        .collect();
+
+    let vertex = |x, y| y * (size + 1) + x;
+    let indices = (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            [
+                vertex(x, y),
+                vertex(x + 1, y),
+                vertex(x, y + 1),
+                vertex(x + 1, y),
+                vertex(x + 1, y + 1),
+                vertex(x, y + 1),
+            ]
+        })
+        .collect();
+
+    (positions, indices)
+}
+
+//maps the local indices of every meshlet back to the source vertex buffer
+fn source_indices(mesh: &MeshletMesh) -> Vec<u32> {
+    mesh.meshlets
+        .iter()
+        .flat_map(|meshlet| {
+            let vertex_indices = mesh.meshlet_vertex_indices(meshlet);
+            mesh.meshlet_primitive_indices(meshlet)
+                .iter()
+                .map(|local| vertex_indices[*local as usize])
+        })
+        .collect()
+}
+
+#[test]
+fn meshlets_respect_limits() {
+    let (positions, indices) = grid(8);
+
+    for (max_vertices, max_primitives) in [(3, 1), (4, 126), (8, 4), (64, 126), (256, 2)] {
+        let mesh =
+            meshlet::build_meshlets(&positions, &indices, max_vertices, max_primitives).unwrap();
+
+        assert_eq!(mesh.meshlets.len(), mesh.bounds.len());
+        for meshlet in &mesh.meshlets {
+            assert!(meshlet.vertex_count as usize <= max_vertices);
+            assert!(meshlet.primitive_count as usize <= max_primitives);
+            assert!(meshlet.primitive_count > 0);
+        }
+
+        let primitive_count: u32 = mesh.meshlets.iter().map(|m| m.primitive_count).sum();
+        assert_eq!(primitive_count as usize, indices.len() / 3);
+    }
+}
+
+#[test]
+fn meshlets_reproduce_source_triangles() {
+    let (positions, indices) = grid(8);
+    let mesh = meshlet::build_meshlets(&positions, &indices, 8, 4).unwrap();
+
+    assert_eq!(source_indices(&mesh), indices);
+}
+
+#[test]
+fn shared_vertices_are_remapped_once() {
+    let positions = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.0),
+    ];
+    let indices = [3, 1, 2, 1, 0, 2];
+    let mesh = meshlet::build_meshlets(&positions, &indices, 64, 126).unwrap();
+
+    assert_eq!(mesh.meshlets.len(), 1);
+    let meshlet = &mesh.meshlets[0];
+    assert_eq!(meshlet.vertex_count, 4);
+    assert_eq!(meshlet.primitive_count, 2);
+    assert_eq!(mesh.meshlet_vertex_indices(meshlet), [3, 1, 2, 0]);
+    assert_eq!(mesh.meshlet_primitive_indices(meshlet), [0, 1, 2, 1, 3, 2]);
+}
+
+#[test]
+fn vertex_limit_starts_a_new_meshlet() {
+    let (positions, indices) = grid(1);
+    let mesh = meshlet::build_meshlets(&positions, &indices, 3, 126).unwrap();
+
+    assert_eq!(mesh.meshlets.len(), 2);
+    assert_eq!(mesh.meshlets[1].vertex_offset, 3);
+    assert_eq!(mesh.meshlets[1].primitive_offset, 3);
+    assert_eq!(mesh.meshlet_vertex_indices(&mesh.meshlets[1]), [1, 3, 2]);
+    assert_eq!(mesh.meshlet_primitive_indices(&mesh.meshlets[1]), [0, 1, 2]);
+}
+
+#[test]
+fn bounds_of_a_flat_quad() {
+    let (positions, indices) = grid(1);
+    let mesh = meshlet::build_meshlets(&positions, &indices, 64, 126).unwrap();
+    let bounds = mesh.bounds[0];
+
+    assert_eq!(bounds.center, Vec3::new(0.5, 0.5, 0.0));
+    assert!((bounds.radius - 0.5f32.sqrt()).abs() < 1e-6);
+    //counter clockwise in the xy plane, so every normal is +z
+    assert_eq!(bounds.cone_axis, Vec3::Z);
+    assert_eq!(bounds.cone_cutoff, 0.0);
+}
+
+#[test]
+fn opposing_normals_disable_cone_culling() {
+    let positions = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    ];
+    let indices = [0, 1, 2, 0, 2, 1];
+    let mesh = meshlet::build_meshlets(&positions, &indices, 64, 126).unwrap();
+
+    assert_eq!(mesh.bounds[0].cone_axis, Vec3::ZERO);
+    assert_eq!(mesh.bounds[0].cone_cutoff, 1.0);
+}
+
+#[test]
+fn invalid_input_is_rejected() {
+    let (positions, indices) = grid(1);
+
+    assert!(meshlet::build_meshlets(&positions, &indices[..4], 64, 126).is_err());
+    assert!(meshlet::build_meshlets(&positions, &indices, 2, 126).is_err());
+    assert!(meshlet::build_meshlets(&positions, &indices, 257, 126).is_err());
+    assert!(meshlet::build_meshlets(&positions, &indices, 64, 0).is_err());
+    assert!(meshlet::build_meshlets(&positions[..3], &indices, 64, 126).is_err());
+}
+
+#[test]
+fn append_offsets_meshlets_and_vertices() {
+    let (positions, indices) = grid(4);
+    let first = meshlet::build_meshlets(&positions, &indices, 16, 16).unwrap();
+    let second = meshlet::build_meshlets(&positions, &indices, 64, 126).unwrap();
+
+    let mut combined = first.clone();
+    combined.append(&second, positions.len() as u32);
+
+    assert_eq!(
+        combined.meshlets.len(),
+        first.meshlets.len() + second.meshlets.len()
+    );
+    assert_eq!(combined.bounds.len(), combined.meshlets.len());
+
+    //the appended meshlets reference the second copy of the vertices
+    let expected: Vec<_> = source_indices(&first)
+        .into_iter()
+        .chain(
+            source_indices(&second)
+                .into_iter()
+                .map(|index| index + positions.len() as u32),
+        )
+        .collect();
+    assert_eq!(source_indices(&combined), expected);
+}
@@ -0,0 +1,134 @@
+use glam::{Vec2, Vec3};
+use vulkan_experinments::asset::obj;
+
+fn parse(source: &str) -> anyhow::Result<vulkan_experinments::asset::Mesh> {
+    obj::parse(source.as_bytes())
+}
+
+//the full context chain, e.g. "line 3: invalid position index in \"0\": index 0 is not valid"
+fn parse_error(source: &str) -> String {
+    format!("{:#}", parse(source).unwrap_err())
+}
+
+#[test]
+fn quad_is_triangulated_as_a_fan() {
+    let mesh = parse(
+        "v 0 0 0\n\
+         v 1 0 0\n\
+         v 1 1 0\n\
+         v 0 1 0\n\
+         f 1 2 3 4\n",
+    )
+    .unwrap();
+
+    assert_eq!(mesh.vertices.len(), 4);
+    assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+    assert_eq!(mesh.groups.len(), 1);
+    assert_eq!(mesh.groups[0].index_count, 6);
+}
+
+#[test]
+fn attributes_and_negative_indices_are_resolved() {
+    let mesh = parse(
+        "v 0 0 0 # comment\n\
+         v 1 0 0\n\
+         v 0 1 0\n\
+         vt 0.5 0.25\n\
+         vn 0 0 -1\n\
+         f -3/-1/-1 -2/1/1 -1/1/1\n",
+    )
+    .unwrap();
+
+    assert_eq!(mesh.vertices[1].position, Vec3::new(1.0, 0.0, 0.0));
+    assert!(mesh
+        .vertices
+        .iter()
+        .all(|v| v.tex_coord == Vec2::new(0.5, 0.25)));
+    assert!(mesh
+        .vertices
+        .iter()
+        .all(|v| v.normal == Vec3::new(0.0, 0.0, -1.0)));
+}
+
+#[test]
+fn groups_split_the_index_buffer() {
+    let mesh = parse(
+        "v 0 0 0\n\
+         v 1 0 0\n\
+         v 0 1 0\n\
+         g first\n\
+         f 1 2 3\n\
+         g empty\n\
+         o second part\n\
+         f 1 3 2\n\
+         f 2 3 1\n",
+    )
+    .unwrap();
+
+    let groups: Vec<_> = mesh
+        .groups
+        .iter()
+        .map(|group| (group.name.as_str(), group.index_offset, group.index_count))
+        .collect();
+    assert_eq!(groups, [("first", 0, 3), ("second part", 3, 6)]);
+}
+
+#[test]
+fn only_missing_normals_are_computed() {
+    let mesh = parse(
+        "v 0 0 0\n\
+         v 1 0 0\n\
+         v 0 1 0\n\
+         vn 1 0 0\n\
+         f 1//1 2//1 3//1\n\
+         f 1 2 3\n",
+    )
+    .unwrap();
+
+    assert_eq!(mesh.vertices.len(), 6);
+    assert!(mesh.vertices[..3]
+        .iter()
+        .all(|v| v.normal == Vec3::new(1.0, 0.0, 0.0)));
+    //counter clockwise in the xy plane
+    assert!(mesh.vertices[3..].iter().all(|v| v.normal == Vec3::Z));
+}
+
+#[test]
+fn errors_report_the_line_number() {
+    let cases = [
+        ("v 0 0 0\nv 1 0\n", "line 2: missing component"),
+        ("v 0 0 x\n", "line 1: invalid number \"x\": invalid float literal"),
+        (
+            "v 0 0 0\n\nf 1 1\n",
+            "line 3: face has 2 vertices, expected at least 3",
+        ),
+        (
+            "v 0 0 0\nf 0 1 1\n",
+            "line 2: invalid position index in \"0\": index 0 is not valid",
+        ),
+        (
+            "v 0 0 0\nf 1 1 4\n",
+            "line 2: invalid position index in \"4\": index 4 is out of range for 1 elements",
+        ),
+        (
+            "v 0 0 0\nf 1 1 -2\n",
+            "line 2: invalid position index in \"-2\": index -2 is out of range for 1 elements",
+        ),
+        (
+            "v 0 0 0\nf 1/1 1 1\n",
+            "line 2: invalid texture coordinate index in \"1/1\": index 1 is out of range for 0 elements",
+        ),
+        (
+            "v 0 0 0\nf 1//1 1 1\n",
+            "line 2: invalid normal index in \"1//1\": index 1 is out of range for 0 elements",
+        ),
+        (
+            "v 0 0 0\nvt 0 0\nvn 0 0 1\nf 1/1/1/1 1 1\n",
+            "line 4: too many components in face vertex \"1/1/1/1\"",
+        ),
+    ];
+
+    for (source, expected) in cases {
+        assert_eq!(parse_error(source), expected, "{:?}", source);
+    }
+}
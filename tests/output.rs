@@ -0,0 +1,122 @@
+use ash::vk;
+use vulkan_experinments::render::{
+    config::HdrOutput,
+    output::{self, OutputTransform},
+};
+
+fn surface_format(format: vk::Format, color_space: vk::ColorSpaceKHR) -> vk::SurfaceFormatKHR {
+    vk::SurfaceFormatKHR::default()
+        .format(format)
+        .color_space(color_space)
+}
+
+fn typical_formats() -> Vec<vk::SurfaceFormatKHR> {
+    vec![
+        surface_format(
+            vk::Format::B8G8R8A8_UNORM,
+            vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        ),
+        surface_format(vk::Format::B8G8R8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR),
+        surface_format(
+            vk::Format::A2B10G10R10_UNORM_PACK32,
+            vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+        ),
+        surface_format(
+            vk::Format::R16G16B16A16_SFLOAT,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+        ),
+    ]
+}
+
+#[test]
+fn sdr_prefers_srgb_formats() {
+    let (chosen, transform) = output::choose_surface_format(&typical_formats(), None).unwrap();
+
+    assert_eq!(chosen.format, vk::Format::B8G8R8A8_SRGB);
+    assert_eq!(chosen.color_space, vk::ColorSpaceKHR::SRGB_NONLINEAR);
+    assert_eq!(transform, OutputTransform::Srgb);
+}
+
+#[test]
+fn unorm_formats_are_encoded_in_the_shader() {
+    let formats = [surface_format(
+        vk::Format::R8G8B8A8_UNORM,
+        vk::ColorSpaceKHR::SRGB_NONLINEAR,
+    )];
+    let (chosen, transform) = output::choose_surface_format(&formats, None).unwrap();
+
+    assert_eq!(chosen.format, vk::Format::R8G8B8A8_UNORM);
+    assert_eq!(transform, OutputTransform::SrgbEncode);
+}
+
+#[test]
+fn hdr_is_used_when_supported() {
+    let (chosen, transform) =
+        output::choose_surface_format(&typical_formats(), Some(HdrOutput::Hdr10)).unwrap();
+    assert_eq!(chosen.format, vk::Format::A2B10G10R10_UNORM_PACK32);
+    assert_eq!(chosen.color_space, vk::ColorSpaceKHR::HDR10_ST2084_EXT);
+    assert_eq!(transform, OutputTransform::Hdr10);
+
+    let (chosen, transform) =
+        output::choose_surface_format(&typical_formats(), Some(HdrOutput::ScRgb)).unwrap();
+    assert_eq!(chosen.format, vk::Format::R16G16B16A16_SFLOAT);
+    assert_eq!(
+        chosen.color_space,
+        vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT
+    );
+    assert_eq!(transform, OutputTransform::ScRgb);
+}
+
+#[test]
+fn unsupported_hdr_falls_back_to_sdr() {
+    let formats = [surface_format(
+        vk::Format::B8G8R8A8_SRGB,
+        vk::ColorSpaceKHR::SRGB_NONLINEAR,
+    )];
+    let (chosen, transform) =
+        output::choose_surface_format(&formats, Some(HdrOutput::Hdr10)).unwrap();
+
+    assert_eq!(chosen.format, vk::Format::B8G8R8A8_SRGB);
+    assert_eq!(transform, OutputTransform::Srgb);
+    assert!(!transform.is_hdr());
+}
+
+#[test]
+fn undefined_format_allows_any() {
+    let formats = [surface_format(
+        vk::Format::UNDEFINED,
+        vk::ColorSpaceKHR::SRGB_NONLINEAR,
+    )];
+    let (chosen, transform) = output::choose_surface_format(&formats, None).unwrap();
+
+    assert_eq!(chosen.format, vk::Format::B8G8R8A8_SRGB);
+    assert_eq!(transform, OutputTransform::Srgb);
+}
+
+#[test]
+fn fallback_formats_use_a_matching_transform() {
+    let formats = [
+        surface_format(
+            vk::Format::R16G16B16A16_SFLOAT,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+        ),
+        surface_format(
+            vk::Format::A2B10G10R10_UNORM_PACK32,
+            vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        ),
+    ];
+    let (chosen, transform) = output::choose_surface_format(&formats, None).unwrap();
+
+    assert_eq!(chosen.format, vk::Format::A2B10G10R10_UNORM_PACK32);
+    assert_eq!(transform, OutputTransform::SrgbEncode);
+}
+
+#[test]
+fn formats_without_a_transform_are_rejected() {
+    let formats = [surface_format(
+        vk::Format::R16G16B16A16_SFLOAT,
+        vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+    )];
+
+    assert!(output::choose_surface_format(&formats, None).is_err());
+}
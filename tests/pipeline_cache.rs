@@ -0,0 +1,92 @@
+use ash::vk;
+use vulkan_experinments::render::pipeline_cache::{self, HEADER_SIZE};
+
+fn properties() -> vk::PhysicalDeviceProperties {
+    vk::PhysicalDeviceProperties {
+        vendor_id: 0x10de,
+        device_id: 0x2684,
+        driver_version: 0x8a4c_4000,
+        pipeline_cache_uuid: *b"0123456789abcdef",
+        ..Default::default()
+    }
+}
+
+//a header as the driver of properties() would write it, followed by some opaque data
+fn cache_data(properties: &vk::PhysicalDeviceProperties) -> Vec<u8> {
+    let mut data = Vec::new();
+    for word in [
+        HEADER_SIZE as u32,
+        1,
+        properties.vendor_id,
+        properties.device_id,
+    ] {
+        data.extend(word.to_le_bytes());
+    }
+    data.extend(properties.pipeline_cache_uuid);
+    data.extend([0xab; 64]);
+    data
+}
+
+#[test]
+fn matching_header_is_accepted() {
+    let properties = properties();
+    pipeline_cache::validate_header(&cache_data(&properties), &properties).unwrap();
+}
+
+#[test]
+fn foreign_caches_are_rejected() {
+    let data = cache_data(&properties());
+
+    let other_device = vk::PhysicalDeviceProperties {
+        device_id: 0x2704,
+        ..properties()
+    };
+    assert_eq!(
+        pipeline_cache::validate_header(&data, &other_device)
+            .unwrap_err()
+            .to_string(),
+        "Created by device 10de:2684 instead of 10de:2704"
+    );
+
+    let other_driver = vk::PhysicalDeviceProperties {
+        pipeline_cache_uuid: *b"fedcba9876543210",
+        ..properties()
+    };
+    assert!(pipeline_cache::validate_header(&data, &other_driver).is_err());
+}
+
+#[test]
+fn malformed_headers_are_rejected() {
+    let properties = properties();
+    let data = cache_data(&properties);
+
+    assert!(pipeline_cache::validate_header(&[], &properties).is_err());
+    assert!(pipeline_cache::validate_header(&data[..HEADER_SIZE - 1], &properties).is_err());
+
+    let mut wrong_version = data.clone();
+    wrong_version[4..8].copy_from_slice(&2u32.to_le_bytes());
+    assert!(pipeline_cache::validate_header(&wrong_version, &properties).is_err());
+
+    //a header size pointing past the end of the data
+    let mut wrong_size = data.clone();
+    wrong_size[0..4].copy_from_slice(&(data.len() as u32 + 1).to_le_bytes());
+    assert!(pipeline_cache::validate_header(&wrong_size, &properties).is_err());
+}
+
+#[test]
+fn file_name_includes_driver_version() {
+    let properties = properties();
+    let other_driver = vk::PhysicalDeviceProperties {
+        driver_version: properties.driver_version + 1,
+        ..properties
+    };
+
+    assert_eq!(
+        pipeline_cache::file_name(&properties),
+        "pipeline-cache-10de-2684-8a4c4000-30313233343536373839616263646566.bin"
+    );
+    assert_ne!(
+        pipeline_cache::file_name(&properties),
+        pipeline_cache::file_name(&other_driver)
+    );
+}
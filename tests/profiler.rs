@@ -0,0 +1,100 @@
+use vulkan_experinments::render::profiler::{GpuProfile, ScopeTiming, MAX_TRACE_FRAMES};
+
+fn timing(name: &'static str, depth: u32, start_ms: f64, duration_ms: f64) -> ScopeTiming {
+    ScopeTiming {
+        name,
+        depth,
+        start_ms,
+        duration_ms,
+    }
+}
+
+fn chrome_trace(profile: &GpuProfile) -> String {
+    let mut trace = Vec::new();
+    profile.write_chrome_trace(&mut trace).unwrap();
+    String::from_utf8(trace).unwrap()
+}
+
+#[test]
+fn scopes_are_aggregated_in_first_seen_order() {
+    let mut profile = GpuProfile::default();
+    profile.add_frame(vec![
+        timing("frame", 0, 0.0, 4.0),
+        timing("mesh pass", 1, 0.5, 3.0),
+    ]);
+    profile.add_frame(vec![
+        timing("frame", 0, 10.0, 2.0),
+        timing("output pass", 1, 10.5, 1.0),
+        timing("mesh pass", 1, 11.5, 0.5),
+    ]);
+
+    let names: Vec<_> = profile.scopes.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, ["frame", "mesh pass", "output pass"]);
+
+    let frame = profile.scope("frame").unwrap();
+    assert_eq!(frame.count, 2);
+    assert_eq!(frame.total_ms, 6.0);
+    assert_eq!(frame.min_ms, 2.0);
+    assert_eq!(frame.max_ms, 4.0);
+    assert_eq!(frame.last_ms, 2.0);
+    assert_eq!(frame.average_ms(), 3.0);
+
+    let output_pass = profile.scope("output pass").unwrap();
+    assert_eq!(output_pass.count, 1);
+    assert_eq!(output_pass.min_ms, 1.0);
+
+    assert!(profile.scope("missing").is_none());
+    assert_eq!(
+        profile.to_string(),
+        "frame 3.00 ms, mesh pass 1.75 ms, output pass 1.00 ms"
+    );
+}
+
+#[test]
+fn empty_frames_are_ignored() {
+    let mut profile = GpuProfile::default();
+    profile.add_frame(Vec::new());
+
+    assert!(profile.scopes.is_empty());
+    assert_eq!(
+        chrome_trace(&profile),
+        "{\"traceEvents\":[\n],\"displayTimeUnit\":\"ms\"}"
+    );
+}
+
+#[test]
+fn chrome_trace_has_one_event_per_scope() {
+    let mut profile = GpuProfile::default();
+    profile.add_frame(vec![
+        timing("frame", 0, 1.0, 2.5),
+        timing("say \"hi\"\\\n", 1, 1.25, 0.001),
+    ]);
+
+    assert_eq!(
+        chrome_trace(&profile),
+        concat!(
+            "{\"traceEvents\":[",
+            "\n{\"name\":\"frame\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":1000.000,\"dur\":2500.000,\"pid\":0,\"tid\":0,\"args\":{\"depth\":0}},",
+            "\n{\"name\":\"say \\\"hi\\\"\\\\\\u000a\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":1250.000,\"dur\":1.000,\"pid\":0,\"tid\":0,\"args\":{\"depth\":1}}",
+            "\n],\"displayTimeUnit\":\"ms\"}"
+        )
+    );
+}
+
+#[test]
+fn chrome_trace_keeps_only_the_last_frames() {
+    let mut profile = GpuProfile::default();
+    for frame in 0..MAX_TRACE_FRAMES + 2 {
+        profile.add_frame(vec![timing("frame", 0, frame as f64, 1.0)]);
+    }
+
+    let trace = chrome_trace(&profile);
+    assert_eq!(trace.matches("\"ph\":\"X\"").count(), MAX_TRACE_FRAMES);
+    assert!(!trace.contains("\"ts\":1000.000,"));
+    assert!(trace.contains("\"ts\":2000.000,"));
+    //the statistics still cover every frame
+    assert_eq!(
+        profile.scope("frame").unwrap().count,
+        MAX_TRACE_FRAMES as u64 + 2
+    );
+}
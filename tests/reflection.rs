@@ -0,0 +1,292 @@
+use ash::vk;
+use vulkan_experinments::render::{
+    reflection::{self, DescriptorBinding, ShaderReflection},
+    spirv,
+};
+
+//the fixtures are assembled by tests/data/reflection/generate.py
+fn load(bytes: &[u8]) -> Vec<u32> {
+    spirv::from_bytes(bytes).unwrap()
+}
+
+fn mesh() -> ShaderReflection {
+    ShaderReflection::parse(&load(include_bytes!("data/reflection/mesh.spv"))).unwrap()
+}
+
+fn fragment() -> ShaderReflection {
+    ShaderReflection::parse(&load(include_bytes!("data/reflection/fragment.spv"))).unwrap()
+}
+
+fn binding(
+    set: u32,
+    binding: u32,
+    descriptor_type: vk::DescriptorType,
+    descriptor_count: u32,
+    stage_flags: vk::ShaderStageFlags,
+    name: Option<&str>,
+) -> DescriptorBinding {
+    DescriptorBinding {
+        set,
+        binding,
+        descriptor_type,
+        descriptor_count,
+        stage_flags,
+        name: name.map(str::to_owned),
+    }
+}
+
+#[test]
+fn mesh_shader() {
+    let mesh = mesh();
+
+    assert_eq!(mesh.stage, vk::ShaderStageFlags::MESH_EXT);
+    assert_eq!(mesh.entry_point, "main");
+    assert_eq!(mesh.local_size, Some([32, 1, 1]));
+    assert_eq!(mesh.max_output_vertices, Some(64));
+    assert_eq!(mesh.max_output_primitives, Some(126));
+    assert_eq!(
+        mesh.bindings,
+        [
+            binding(
+                0,
+                0,
+                vk::DescriptorType::UNIFORM_BUFFER,
+                1,
+                vk::ShaderStageFlags::MESH_EXT,
+                Some("ubo")
+            ),
+            binding(
+                0,
+                1,
+                vk::DescriptorType::STORAGE_BUFFER,
+                1,
+                vk::ShaderStageFlags::MESH_EXT,
+                Some("vertices")
+            ),
+        ]
+    );
+
+    //a vec4 followed by a uint
+    let push_constant_range = mesh.push_constant_range.unwrap();
+    assert_eq!(push_constant_range.offset, 0);
+    assert_eq!(push_constant_range.size, 20);
+    assert_eq!(
+        push_constant_range.stage_flags,
+        vk::ShaderStageFlags::MESH_EXT
+    );
+}
+
+#[test]
+fn fragment_shader() {
+    let fragment = fragment();
+
+    assert_eq!(fragment.stage, vk::ShaderStageFlags::FRAGMENT);
+    assert_eq!(fragment.local_size, None);
+    assert_eq!(fragment.max_output_vertices, None);
+    assert_eq!(
+        fragment.bindings,
+        [
+            binding(
+                0,
+                0,
+                vk::DescriptorType::UNIFORM_BUFFER,
+                1,
+                vk::ShaderStageFlags::FRAGMENT,
+                None
+            ),
+            binding(
+                1,
+                0,
+                vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                4,
+                vk::ShaderStageFlags::FRAGMENT,
+                Some("textures")
+            ),
+        ]
+    );
+    assert_eq!(fragment.push_constant_range.unwrap().size, 16);
+}
+
+#[test]
+fn merge_combines_stages() {
+    let pipeline = reflection::merge(&[mesh(), fragment()]).unwrap();
+    let both = vk::ShaderStageFlags::MESH_EXT | vk::ShaderStageFlags::FRAGMENT;
+
+    assert_eq!(pipeline.sets.keys().copied().collect::<Vec<_>>(), [0, 1]);
+    assert_eq!(
+        pipeline.binding(0, 0),
+        Some(&binding(
+            0,
+            0,
+            vk::DescriptorType::UNIFORM_BUFFER,
+            1,
+            both,
+            Some("ubo")
+        ))
+    );
+    assert_eq!(
+        pipeline.binding(0, 1).unwrap().stage_flags,
+        vk::ShaderStageFlags::MESH_EXT
+    );
+    assert_eq!(pipeline.binding(0, 2), None);
+
+    let pool_sizes = pipeline.descriptor_pool_sizes(0);
+    assert_eq!(pool_sizes.len(), 2);
+    assert!(pool_sizes
+        .iter()
+        .all(|pool_size| pool_size.descriptor_count == 1));
+    assert_eq!(pipeline.descriptor_pool_sizes(1)[0].descriptor_count, 4);
+
+    //one range large enough for the larger block
+    assert_eq!(pipeline.push_constant_ranges.len(), 1);
+    assert_eq!(pipeline.push_constant_ranges[0].size, 20);
+    assert_eq!(pipeline.push_constant_ranges[0].stage_flags, both);
+}
+
+#[test]
+fn merge_rejects_mismatched_bindings() {
+    let mismatched = ShaderReflection::parse(&load(include_bytes!(
+        "data/reflection/mismatched_fragment.spv"
+    )))
+    .unwrap();
+    assert_eq!(
+        mismatched.bindings[0].descriptor_type,
+        vk::DescriptorType::UNIFORM_BUFFER
+    );
+
+    let error = reflection::merge(&[mesh(), mismatched]).unwrap_err();
+    assert!(error.to_string().starts_with("Binding 0.1 is"), "{}", error);
+}
+
+#[test]
+fn layout_compatibility() {
+    let pipeline = reflection::merge(&[mesh(), fragment()]).unwrap();
+
+    //a subset of the bindings and push constants can use the layout, the names do not matter
+    let mut subset = reflection::merge(&[mesh()]).unwrap();
+    subset.sets.get_mut(&0).unwrap()[0].name = Some(String::from("renamed"));
+    pipeline.ensure_layout_compatible(&subset).unwrap();
+    pipeline.ensure_layout_compatible(&pipeline).unwrap();
+
+    let error = subset.ensure_layout_compatible(&pipeline).unwrap_err();
+    assert!(error.to_string().starts_with("Binding 0.0 is"), "{}", error);
+
+    let mut different_type = subset.clone();
+    different_type.sets.get_mut(&0).unwrap()[1].descriptor_type =
+        vk::DescriptorType::UNIFORM_BUFFER;
+    let error = pipeline
+        .ensure_layout_compatible(&different_type)
+        .unwrap_err();
+    assert!(error.to_string().starts_with("Binding 0.1 is"), "{}", error);
+
+    let mut new_binding = subset.clone();
+    new_binding.sets.get_mut(&0).unwrap()[1].binding = 7;
+    assert!(pipeline.ensure_layout_compatible(&new_binding).is_err());
+
+    let mut larger_push_constants = subset;
+    larger_push_constants.push_constant_ranges[0].size = 32;
+    let error = pipeline
+        .ensure_layout_compatible(&larger_push_constants)
+        .unwrap_err();
+    assert!(error.to_string().starts_with("Push constants"), "{}", error);
+}
+
+#[test]
+fn parse_rejects_malformed_modules() {
+    let code = load(include_bytes!("data/reflection/mesh.spv"));
+
+    let mut wrong_magic = code.clone();
+    wrong_magic[0] = wrong_magic[0].swap_bytes();
+    assert!(ShaderReflection::parse(&wrong_magic).is_err());
+
+    assert!(ShaderReflection::parse(&code[..3]).is_err());
+
+    //the first instruction claims to run past the end of the module
+    let mut truncated = code.clone();
+    truncated[5] = 1000 << 16 | (truncated[5] & 0xffff);
+    assert!(ShaderReflection::parse(&truncated).is_err());
+
+    let mut empty_instruction = code;
+    empty_instruction[5] &= 0xffff;
+    assert!(ShaderReflection::parse(&empty_instruction).is_err());
+
+    //an OpEntryPoint with only the execution model
+    let entry_point_without_name = [spirv::MAGIC, 0x0001_0000, 0, 1, 0, 2 << 16 | 15, 5];
+    assert!(ShaderReflection::parse(&entry_point_without_name).is_err());
+}
+
+//the shaders build.rs embeds, which render_ctx derives its layouts from
+#[cfg(not(feature = "shaders-from-disk"))]
+#[test]
+fn embedded_shaders() {
+    use std::path::Path;
+    use vulkan_experinments::render::{
+        geometry, output,
+        shaders::{self, SHADERS},
+    };
+
+    for (name, code) in SHADERS {
+        ShaderReflection::parse(code).unwrap_or_else(|e| panic!("{}: {:#}", name, e));
+    }
+
+    let stages: Vec<_> = [
+        "example.ext.task.spv",
+        "example.ext.mesh.spv",
+        "example.frag.spv",
+    ]
+    .iter()
+    .map(|name| ShaderReflection::parse(&shaders::load(Path::new(""), name).unwrap()).unwrap())
+    .collect();
+    assert_eq!(
+        stages[0].local_size,
+        Some([geometry::TASK_WORKGROUP_SIZE, 1, 1])
+    );
+
+    let pipeline = reflection::merge(&stages).unwrap();
+    assert_eq!(
+        pipeline.binding(0, 0).unwrap().stage_flags,
+        vk::ShaderStageFlags::TASK_EXT | vk::ShaderStageFlags::MESH_EXT
+    );
+    assert_eq!(
+        pipeline
+            .binding(0, geometry::MESHLET_BOUNDS_BINDING)
+            .unwrap()
+            .stage_flags,
+        vk::ShaderStageFlags::TASK_EXT
+    );
+    assert_eq!(pipeline.push_constant_ranges.len(), 1);
+    assert_eq!(
+        pipeline.push_constant_ranges[0].size,
+        geometry::DRAW_PUSH_CONSTANTS_SIZE
+    );
+    assert_eq!(
+        pipeline.push_constant_ranges[0].stage_flags,
+        vk::ShaderStageFlags::TASK_EXT | vk::ShaderStageFlags::MESH_EXT
+    );
+    for binding in [
+        geometry::VERTEX_BINDING,
+        geometry::MESHLET_BINDING,
+        geometry::VERTEX_INDEX_BINDING,
+        geometry::PRIMITIVE_INDEX_BINDING,
+    ] {
+        let binding = pipeline.binding(0, binding).unwrap();
+        assert_eq!(binding.descriptor_type, vk::DescriptorType::STORAGE_BUFFER);
+        assert_eq!(binding.stage_flags, vk::ShaderStageFlags::MESH_EXT);
+    }
+
+    let output_stages: Vec<_> = [output::VERTEX_SHADER_PATH, output::FRAGMENT_SHADER_PATH]
+        .iter()
+        .map(|name| ShaderReflection::parse(&shaders::load(Path::new(""), name).unwrap()).unwrap())
+        .collect();
+    let output_pipeline = reflection::merge(&output_stages).unwrap();
+    assert_eq!(
+        output_pipeline.binding(0, 0).unwrap().descriptor_type,
+        vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+    );
+    assert_eq!(output_pipeline.push_constant_ranges.len(), 1);
+    assert_eq!(output_pipeline.push_constant_ranges[0].size, 8);
+    assert_eq!(
+        output_pipeline.push_constant_ranges[0].stage_flags,
+        vk::ShaderStageFlags::FRAGMENT
+    );
+}
@@ -0,0 +1,173 @@
+use glam::{Mat4, Vec3, Vec4};
+use vulkan_experinments::asset::{scene::Scene, Mesh};
+
+const TRIANGLE_POSITIONS: [[f32; 3]; 3] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+
+//a .glb with a single triangle mesh in its binary chunk, nodes and scenes are given as json
+fn glb(nodes: &str, scenes: &str) -> Vec<u8> {
+    let bin: Vec<u8> = TRIANGLE_POSITIONS
+        .iter()
+        .flatten()
+        .flat_map(|f| f.to_le_bytes())
+        .collect();
+
+    let json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "buffers": [{{ "byteLength": {length} }}],
+            "bufferViews": [{{ "buffer": 0, "byteLength": {length} }}],
+            "accessors": [{{
+                "bufferView": 0,
+                "componentType": 5126,
+                "count": 3,
+                "type": "VEC3",
+                "min": [0, 0, 0],
+                "max": [1, 1, 0]
+            }}],
+            "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }} }}] }}],
+            "nodes": {nodes},
+            "scenes": {scenes}
+        }}"#,
+        length = bin.len(),
+        nodes = nodes,
+        scenes = scenes,
+    );
+
+    let mut json = json.into_bytes();
+    json.resize((json.len() + 3) & !3, b' ');
+
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::with_capacity(length);
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&(length as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&bin);
+    glb
+}
+
+fn load(nodes: &str, scenes: &str) -> anyhow::Result<Scene> {
+    Scene::from_slice(&glb(nodes, scenes), None)
+}
+
+#[test]
+fn primitive_is_imported_with_computed_normals() {
+    let scene = load(r#"[{ "mesh": 0 }]"#, r#"[{ "nodes": [0] }]"#).unwrap();
+
+    assert_eq!(scene.meshes.len(), 1);
+    let mesh = &scene.meshes[0].primitives[0].mesh;
+    assert_eq!(mesh.positions(), TRIANGLE_POSITIONS.map(Vec3::from));
+    //without an index accessor every vertex is used once
+    assert_eq!(mesh.indices, [0, 1, 2]);
+    assert!(mesh.vertices.iter().all(|v| v.normal == Vec3::Z));
+    assert_eq!(scene.meshes[0].primitives[0].material, None);
+}
+
+#[test]
+fn world_transforms_follow_the_hierarchy() {
+    let scene = load(
+        r#"[
+            { "children": [1], "translation": [1, 2, 3] },
+            { "mesh": 0, "scale": [2, 2, 2] }
+        ]"#,
+        r#"[{ "nodes": [0] }]"#,
+    )
+    .unwrap();
+
+    let expected =
+        Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0)) * Mat4::from_scale(Vec3::splat(2.0));
+    assert_eq!(scene.nodes[1].world_transform, expected);
+
+    let draws: Vec<_> = scene.draws().collect();
+    assert_eq!(draws.len(), 1);
+    assert_eq!(draws[0].transform, expected);
+    assert_eq!((draws[0].mesh_index, draws[0].primitive_index), (0, 0));
+    assert_eq!(
+        draws[0]
+            .transform
+            .transform_point3(draws[0].primitive.mesh.vertices[1].position),
+        Vec3::new(3.0, 2.0, 3.0)
+    );
+}
+
+#[test]
+fn mesh_is_drawn_once_with_the_default_material() {
+    let scene = Scene::from_mesh(Mesh::triangle());
+
+    let draws: Vec<_> = scene.draws().collect();
+    assert_eq!(draws.len(), 1);
+    assert_eq!(draws[0].transform, Mat4::IDENTITY);
+    assert_eq!(draws[0].material.base_color_factor, Vec4::ONE);
+    assert_eq!(draws[0].primitive.mesh.indices, [0, 1, 2]);
+}
+
+#[test]
+fn only_nodes_of_the_active_scene_are_drawn() {
+    let scene = load(
+        r#"[
+            { "mesh": 0, "translation": [1, 0, 0] },
+            { "mesh": 0, "translation": [2, 0, 0] },
+            { "mesh": 0, "translation": [3, 0, 0] }
+        ]"#,
+        r#"[{ "nodes": [2] }, { "nodes": [0, 1] }]"#,
+    )
+    .unwrap();
+
+    let translations: Vec<_> = scene
+        .draws()
+        .map(|draw| draw.transform.w_axis.truncate())
+        .collect();
+    assert_eq!(translations, [Vec3::new(3.0, 0.0, 0.0)]);
+}
+
+#[test]
+fn nodes_without_a_scene_are_drawn_from_their_roots() {
+    let scene = load(
+        r#"[
+            { "mesh": 0, "translation": [1, 0, 0] },
+            { "children": [0, 2], "translation": [0, 1, 0] },
+            { "mesh": 0 }
+        ]"#,
+        "[]",
+    )
+    .unwrap();
+
+    assert_eq!(scene.root_nodes, [1]);
+    let translations: Vec<_> = scene
+        .draws()
+        .map(|draw| draw.transform.w_axis.truncate())
+        .collect();
+    assert_eq!(
+        translations,
+        [Vec3::new(1.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)]
+    );
+}
+
+#[test]
+fn cyclic_hierarchy_is_rejected() {
+    let result = load(
+        r#"[{ "children": [1] }, { "children": [0], "mesh": 0 }]"#,
+        r#"[{ "nodes": [0] }]"#,
+    );
+
+    let error = format!("{:#}", result.unwrap_err());
+    assert!(error.contains("reachable more than once"), "{}", error);
+}
+
+#[test]
+fn update_rejects_shared_children() {
+    let mut scene = load(
+        r#"[{ "children": [2] }, { "children": [] }, { "mesh": 0 }]"#,
+        r#"[{ "nodes": [0, 1] }]"#,
+    )
+    .unwrap();
+
+    scene.nodes[1].children.push(2);
+    assert!(scene.update_world_transforms().is_err());
+    //draws still terminate and visit the shared node once
+    assert_eq!(scene.draws().count(), 1);
+}
@@ -0,0 +1,120 @@
+use ash::vk;
+use vulkan_experinments::render::spirv::{self, SpirvVersion, MAGIC};
+
+//a module header followed by OpCapability Shader
+fn module(version: u32) -> Vec<u32> {
+    vec![MAGIC, version, 0, 1, 0, 2 << 16 | 17, 1]
+}
+
+fn to_bytes(words: &[u32], to_bytes: fn(u32) -> [u8; 4]) -> Vec<u8> {
+    words.iter().flat_map(|&word| to_bytes(word)).collect()
+}
+
+fn error(result: anyhow::Result<impl std::fmt::Debug>) -> String {
+    result.unwrap_err().to_string()
+}
+
+#[test]
+fn little_endian() {
+    let code = module(0x0001_0300);
+    assert_eq!(
+        spirv::from_bytes(&to_bytes(&code, u32::to_le_bytes)).unwrap(),
+        code
+    );
+}
+
+#[test]
+fn big_endian_is_swapped() {
+    let code = module(0x0001_0300);
+    assert_eq!(
+        spirv::from_bytes(&to_bytes(&code, u32::to_be_bytes)).unwrap(),
+        code
+    );
+}
+
+#[test]
+fn unaligned_input_is_copied() {
+    let code = module(0x0001_0000);
+    let mut bytes = vec![0];
+    bytes.extend(to_bytes(&code, u32::to_le_bytes));
+
+    assert_eq!(spirv::from_bytes(&bytes[1..]).unwrap(), code);
+}
+
+#[test]
+fn partial_words_are_rejected() {
+    let mut bytes = to_bytes(&module(0x0001_0000), u32::to_le_bytes);
+    bytes.pop();
+
+    assert_eq!(
+        error(spirv::from_bytes(&bytes)),
+        "SPIR-V must be a whole number of 4 byte words, got 27 bytes"
+    );
+}
+
+#[test]
+fn truncated_header_is_rejected() {
+    let bytes = to_bytes(&[MAGIC, 0x0001_0000], u32::to_le_bytes);
+    assert_eq!(
+        error(spirv::from_bytes(&bytes)),
+        "SPIR-V must start with a 20 byte header, got 8 bytes"
+    );
+    assert!(spirv::from_bytes(&[]).is_err());
+    assert!(spirv::validate(&[MAGIC], SpirvVersion::new(1, 6)).is_err());
+}
+
+#[test]
+fn wrong_magic_is_rejected() {
+    let mut code = module(0x0001_0000);
+    code[0] = 0xdead_beef;
+
+    assert_eq!(
+        error(spirv::from_bytes(&to_bytes(&code, u32::to_le_bytes))),
+        "Invalid SPIR-V magic number 0xdeadbeef, expected 0x07230203"
+    );
+    assert!(spirv::validate(&code, SpirvVersion::new(1, 6)).is_err());
+
+    //e.g. a module read without from_bytes on a machine of the other endianness
+    code[0] = MAGIC.swap_bytes();
+    assert_eq!(
+        error(spirv::validate(&code, SpirvVersion::new(1, 6))),
+        "SPIR-V is in the wrong byte order"
+    );
+}
+
+#[test]
+fn version_is_checked_against_device() {
+    let code = module(0x0001_0500);
+    assert_eq!(
+        spirv::validate(&code, SpirvVersion::new(1, 6)).unwrap(),
+        SpirvVersion::new(1, 5)
+    );
+    assert_eq!(
+        error(spirv::validate(&code, SpirvVersion::new(1, 3))),
+        "SPIR-V 1.5 is newer than the 1.3 supported by the device"
+    );
+
+    //reserved bytes of the version word must be zero
+    assert!(spirv::validate(&module(0x0001_0001), SpirvVersion::new(1, 6)).is_err());
+    assert!(spirv::validate(&module(0x0101_0000), SpirvVersion::new(1, 6)).is_err());
+}
+
+#[test]
+fn max_supported_versions() {
+    assert_eq!(
+        SpirvVersion::max_supported(vk::API_VERSION_1_0),
+        SpirvVersion::new(1, 0)
+    );
+    assert_eq!(
+        SpirvVersion::max_supported(vk::API_VERSION_1_1),
+        SpirvVersion::new(1, 3)
+    );
+    assert_eq!(
+        SpirvVersion::max_supported(vk::API_VERSION_1_2),
+        SpirvVersion::new(1, 5)
+    );
+    assert_eq!(
+        SpirvVersion::max_supported(vk::API_VERSION_1_3),
+        SpirvVersion::new(1, 6)
+    );
+}
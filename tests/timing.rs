@@ -0,0 +1,148 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use vulkan_experinments::timing::{
+    FixedTimestep, FrameLimiter, FrameStats, FrameTimer, FPS_SMOOTHING,
+};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn stats_are_empty_before_the_first_frame() {
+    assert_eq!(FrameTimer::new(4).stats(), FrameStats::default());
+}
+
+#[test]
+fn stats_cover_only_the_window() {
+    let mut frame_timer = FrameTimer::new(4);
+    for delta in [6, 1, 2, 3, 4, 5] {
+        frame_timer.record(ms(delta));
+    }
+
+    let stats = frame_timer.stats();
+    assert_eq!(frame_timer.delta(), ms(5));
+    assert_eq!(stats.min, ms(2));
+    assert_eq!(stats.max, ms(5));
+    assert_eq!(stats.average, Duration::from_micros(3500));
+    //nearest rank over 2, 3, 4, 5
+    assert_eq!(stats.p50, ms(3));
+    assert_eq!(stats.p95, ms(5));
+    assert_eq!(stats.p99, ms(5));
+}
+
+#[test]
+fn percentiles_use_the_nearest_rank() {
+    let mut frame_timer = FrameTimer::new(100);
+    for delta in 1..=100 {
+        frame_timer.record(ms(delta));
+    }
+
+    let stats = frame_timer.stats();
+    assert_eq!(stats.p50, ms(50));
+    assert_eq!(stats.p95, ms(95));
+    assert_eq!(stats.p99, ms(99));
+}
+
+#[test]
+fn fps_are_an_exponential_moving_average() {
+    let mut frame_timer = FrameTimer::new(4);
+
+    frame_timer.record(ms(10));
+    assert!((frame_timer.stats().smoothed_fps - 100.0).abs() < 1e-3);
+
+    frame_timer.record(ms(20));
+    let smoothed_delta = 0.010 + (0.020 - 0.010) * FPS_SMOOTHING;
+    assert!((frame_timer.stats().smoothed_fps - 1.0 / smoothed_delta).abs() < 1e-3);
+}
+
+#[test]
+fn stats_display_in_milliseconds() {
+    let mut frame_timer = FrameTimer::new(4);
+    frame_timer.record(ms(10));
+
+    assert_eq!(
+        frame_timer.stats().to_string(),
+        "100.0 fps | 10.00 ms (min 10.00, max 10.00, p95 10.00, p99 10.00)"
+    );
+}
+
+#[test]
+fn tick_records_the_elapsed_time() {
+    let mut frame_timer = FrameTimer::new(4);
+    thread::sleep(ms(1));
+
+    let delta = frame_timer.tick();
+    assert!(delta >= 0.001);
+    assert_eq!(delta, frame_timer.delta_seconds());
+    assert_eq!(frame_timer.stats().max, frame_timer.delta());
+}
+
+#[test]
+fn fixed_timestep_carries_the_remainder() {
+    let mut timestep = FixedTimestep::new(ms(10), 8);
+
+    assert_eq!(timestep.accumulate(ms(25)), 2);
+    assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+
+    assert_eq!(timestep.accumulate(ms(4)), 0);
+    assert!((timestep.alpha() - 0.9).abs() < 1e-6);
+
+    assert_eq!(timestep.accumulate(ms(1)), 1);
+    assert_eq!(timestep.alpha(), 0.0);
+}
+
+#[test]
+fn fixed_timestep_drops_time_after_a_stall() {
+    let mut timestep = FixedTimestep::new(ms(10), 3);
+
+    //only the fraction of a step is kept, not the 97 skipped steps
+    assert_eq!(timestep.accumulate(ms(1005)), 3);
+    assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+
+    assert_eq!(timestep.accumulate(ms(5)), 1);
+    assert_eq!(timestep.step(), ms(10));
+}
+
+#[test]
+fn frame_limiter_rejects_invalid_limits() {
+    //the reciprocal of the last two does not fit into a Duration
+    for max_fps in [
+        0.0,
+        -1.0,
+        f32::NAN,
+        f32::INFINITY,
+        1e-30,
+        f32::MIN_POSITIVE / 4.0,
+    ] {
+        assert!(FrameLimiter::new(max_fps).is_err(), "{}", max_fps);
+    }
+}
+
+#[test]
+fn frame_limiter_paces_frames() {
+    let mut frame_limiter = FrameLimiter::new(200.0).unwrap();
+    let start = Instant::now();
+    for _ in 0..5 {
+        frame_limiter.wait();
+    }
+
+    //the first frame is due immediately
+    assert!(start.elapsed() >= ms(20));
+}
+
+#[test]
+fn frame_limiter_does_not_catch_up_after_a_stall() {
+    let mut frame_limiter = FrameLimiter::new(200.0).unwrap();
+    frame_limiter.wait();
+    thread::sleep(ms(30));
+
+    //the first frame after the stall is late, the next one is a full frame time after it
+    let start = Instant::now();
+    frame_limiter.wait();
+    frame_limiter.wait();
+    assert!(start.elapsed() >= ms(5));
+}